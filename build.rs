@@ -10,7 +10,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .build_server(false)
             .build_client(true)
             .out_dir(proto_dir)
-            .compile(&["proto/backend.proto"], &["proto/"])?;
+            .compile(&["proto/backend.proto", "proto/health.proto"], &["proto/"])?;
     }
     
     Ok(())
@@ -0,0 +1,88 @@
+//! Image generation endpoint (OpenAI images API compatible)
+
+use axum::{extract::State, Json};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::backend::traits::{GenerateRequest, GenerateResponse};
+use crate::error::Result;
+use crate::response::{ResponseFormat, ResponseHandler};
+use crate::storage::cache::ImageCache;
+use crate::AppState;
+
+/// Generate one or more images, short-circuiting to the cache on an identical request.
+/// The cache only ever stores a single image per key, so multi-image requests (`n >
+/// 1`) skip it entirely rather than risk a cache hit silently truncating the response
+/// down to one image.
+pub async fn generate(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<GenerateRequest>,
+) -> Result<Json<GenerateResponse>> {
+    let cacheable = request.n == 1;
+    let cache_key = ImageCache::request_key(&request);
+
+    if cacheable {
+        if let Some(cached) = state.image_cache.cache_get(&cache_key).await {
+            debug!(key = %cache_key, "Serving generate response from cache");
+            return Ok(Json(GenerateResponse {
+                images: vec![crate::backend::traits::GeneratedImage {
+                    b64_json: Some(crate::response::base64::encode(&cached.data)),
+                    url: None,
+                    revised_prompt: None,
+                    seed: request.seed,
+                }],
+                model: request.model.clone(),
+            }));
+        }
+    }
+
+    let format = ResponseFormat::from_str(&request.response_format);
+    let response = state.request_queue.submit(request.clone(), None).await?;
+
+    if cacheable {
+        if let Some(first) = response.images.first() {
+            if let Some(b64_data) = &first.b64_json {
+                if let Ok(bytes) = crate::response::base64::decode(b64_data) {
+                    state
+                        .image_cache
+                        .cache_put(cache_key, bytes, "image/png".to_string())
+                        .await
+                        .ok();
+                }
+            }
+        }
+    }
+
+    let (settings_url_prefix, auth) = {
+        let settings = state.settings.read().await;
+        (settings.storage.url_prefix.clone(), settings.auth.clone())
+    };
+    let response_handler =
+        ResponseHandler::new(state.storage_backend.clone(), settings_url_prefix.clone());
+    let mut images = response_handler
+        .process_batch(response.images, format)
+        .await?;
+
+    if let Some(signing_key) = crate::middleware::signed_url::load_signing_key(&auth)? {
+        for image in &mut images {
+            if let Some(url) = &image.url {
+                // The image path is everything after the url_prefix, which may itself
+                // contain sharding subdirectories (e.g. "ab/cd/<digest>.png")
+                if let Some(image_path) = url.strip_prefix(&settings_url_prefix) {
+                    let image_path = image_path.trim_start_matches('/');
+                    let token = crate::middleware::signed_url::mint_token(
+                        &signing_key,
+                        image_path,
+                        auth.token_ttl_secs,
+                    );
+                    image.url = Some(format!("{}?token={}", url, token));
+                }
+            }
+        }
+    }
+
+    Ok(Json(GenerateResponse {
+        images,
+        model: response.model,
+    }))
+}
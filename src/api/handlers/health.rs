@@ -0,0 +1,130 @@
+//! Health and metrics endpoints
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::backend::traits::CircuitState;
+use crate::AppState;
+
+/// Liveness response
+#[derive(Serialize)]
+pub struct HealthResponse {
+    status: &'static str,
+}
+
+/// Simple liveness probe; bypassed by auth and rate-limit middleware
+pub async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "healthy" })
+}
+
+/// Per-backend circuit breaker detail, for reporting which backends are in backoff and
+/// for how long
+#[derive(Serialize)]
+pub struct BackendHealthDetail {
+    name: String,
+    healthy: bool,
+    circuit_state: &'static str,
+    consecutive_failures: u32,
+    /// Remaining backoff before the next probe is attempted, in milliseconds; `0` unless
+    /// `circuit_state` is `"open"`
+    backoff_remaining_ms: u64,
+}
+
+/// Per-backend batch queue depth and enqueue/dequeue/drop counters, reported so
+/// operators can see which backends are backing up or shedding load instead of only
+/// the aggregate `queue_pending`/`queue_processed` counters
+#[derive(Serialize)]
+pub struct BackendQueueDetail {
+    name: String,
+    depth: usize,
+    enqueued: u64,
+    dequeued: u64,
+    dropped: u64,
+}
+
+/// Storage backend deduplication snapshot, reported when the configured
+/// `StorageBackend` tracks it (currently only the filesystem backend)
+#[derive(Serialize)]
+pub struct DedupDetail {
+    unique_objects: u64,
+    bytes_saved: u64,
+}
+
+/// Gateway-level metrics snapshot
+#[derive(Serialize)]
+pub struct MetricsResponse {
+    backends_total: usize,
+    backends_healthy: usize,
+    backends_unhealthy: usize,
+    queue_pending: u64,
+    queue_processed: u64,
+    backends: Vec<BackendHealthDetail>,
+    backend_queues: Vec<BackendQueueDetail>,
+    dedup: Option<DedupDetail>,
+}
+
+/// Report backend health and queue statistics
+pub async fn metrics(State(state): State<Arc<AppState>>) -> Json<MetricsResponse> {
+    let (total, healthy, unhealthy) = state.health_manager.get_health_summary().await;
+    let stats = state.request_queue.stats();
+
+    let backend_queues = state
+        .request_queue
+        .batcher_stats()
+        .await
+        .into_iter()
+        .map(|(name, stats)| BackendQueueDetail {
+            name,
+            depth: stats.depth,
+            enqueued: stats.enqueued,
+            dequeued: stats.dequeued,
+            dropped: stats.dropped,
+        })
+        .collect();
+
+    let backends = state
+        .health_manager
+        .get_all_statuses()
+        .into_iter()
+        .map(|(name, status)| {
+            let backoff_remaining_ms = match (status.circuit_state, status.open_since) {
+                (CircuitState::Open, Some(opened)) => {
+                    status.backoff.saturating_sub(opened.elapsed()).as_millis() as u64
+                }
+                _ => 0,
+            };
+
+            BackendHealthDetail {
+                name,
+                healthy: status.healthy,
+                circuit_state: match status.circuit_state {
+                    CircuitState::Closed => "closed",
+                    CircuitState::Open => "open",
+                    CircuitState::HalfOpen => "half_open",
+                },
+                consecutive_failures: status.consecutive_failures,
+                backoff_remaining_ms,
+            }
+        })
+        .collect();
+
+    let dedup = state
+        .storage_backend
+        .dedup_stats()
+        .map(|stats| DedupDetail {
+            unique_objects: stats.unique_objects,
+            bytes_saved: stats.bytes_saved,
+        });
+
+    Json(MetricsResponse {
+        backends_total: total,
+        backends_healthy: healthy,
+        backends_unhealthy: unhealthy,
+        queue_pending: stats.pending,
+        queue_processed: stats.processed,
+        backends,
+        backend_queues,
+        dedup,
+    })
+}
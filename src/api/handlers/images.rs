@@ -0,0 +1,311 @@
+//! HTTP handler for serving generated images, with conditional GET and byte-range support
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::path::Path as StdPath;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+use tokio_util::io::ReaderStream;
+
+use crate::error::{AppError, Result};
+use crate::middleware::signed_url;
+use crate::storage::backend::ReadTransform;
+use crate::storage::cache::{content_integrity, hash_file};
+use crate::AppState;
+
+/// Chunk size used when streaming image bodies, keeping memory flat for large outputs
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Query parameters accepted by the image-serving route
+#[derive(Debug, Deserialize)]
+pub struct ImageQuery {
+    /// Signed access token, required when `AuthConfig.token_signing_key` is configured
+    token: Option<String>,
+    /// Target width in pixels; triggers an on-the-fly resize via
+    /// `StorageBackend::read_transformed` when set alongside `height` and/or `format`
+    width: Option<u32>,
+    height: Option<u32>,
+    /// Output format extension, e.g. `webp`, `avif`, `jpg`. Defaults to the stored
+    /// blob's own format when unset.
+    format: Option<String>,
+    /// Encoder quality (0-100); only honored by formats that support lossy tuning
+    quality: Option<u8>,
+}
+
+impl ImageQuery {
+    fn transform(&self) -> ReadTransform {
+        ReadTransform {
+            width: self.width,
+            height: self.height,
+            format: self.format.clone(),
+            quality: self.quality,
+        }
+    }
+}
+
+/// Serve a previously generated image, honoring `If-None-Match`, `If-Modified-Since`,
+/// and `Range` requests instead of always sending the whole file
+pub async fn serve_image(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(query): Query<ImageQuery>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let auth = {
+        let settings = state.settings.read().await;
+        settings.auth.clone()
+    };
+
+    if let Some(signing_key) = signed_url::load_signing_key(&auth)? {
+        let verifying_key = signing_key.verifying_key();
+        let token = query
+            .token
+            .as_deref()
+            .ok_or_else(|| AppError::Forbidden("Missing access token".to_string()))?;
+        signed_url::verify_token(&verifying_key, &filename, token)?;
+    }
+
+    validate_image_key(&filename)?;
+
+    let transform = query.transform();
+    if !transform.is_noop() {
+        return serve_transformed(&state, &filename, &transform, &headers).await;
+    }
+
+    let path = state.storage_backend.get_path(&filename).ok_or_else(|| {
+        AppError::Internal(
+            "Configured storage backend has no local file representation to serve directly"
+                .to_string(),
+        )
+    })?;
+
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .map_err(|_| AppError::InvalidRequest(format!("Image '{}' not found", filename)))?;
+
+    let file_len = metadata.len();
+    let last_modified = metadata.modified().map_err(AppError::Io)?;
+    let last_modified_http = httpdate::fmt_http_date(last_modified);
+    let etag = format!("\"{}\"", hash_file(&path).await?);
+
+    if not_modified(&headers, &etag, last_modified) {
+        return Ok(not_modified_response(&etag, &last_modified_http));
+    }
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, file_len));
+
+    let mut file = File::open(&path).await.map_err(AppError::Io)?;
+
+    let mut response = match range {
+        Some((start, end)) => {
+            file.seek(SeekFrom::Start(start)).await.map_err(AppError::Io)?;
+            let len = end - start + 1;
+            let stream = ReaderStream::with_capacity(file.take(len), CHUNK_SIZE);
+
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, file_len),
+                )
+                .header(header::CONTENT_LENGTH, len.to_string())
+                .body(Body::from_stream(stream))
+                .map_err(|e| AppError::Internal(e.to_string()))?
+        }
+        None => {
+            let stream = ReaderStream::with_capacity(file, CHUNK_SIZE);
+
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, file_len.to_string())
+                .body(Body::from_stream(stream))
+                .map_err(|e| AppError::Internal(e.to_string()))?
+        }
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type_for(&path)));
+    response_headers.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+    response_headers.insert(
+        header::LAST_MODIFIED,
+        HeaderValue::from_str(&last_modified_http).unwrap(),
+    );
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    Ok(response)
+}
+
+/// Serve a resized/re-encoded variant of `filename` per `transform`'s width/height/
+/// format/quality, via `StorageBackend::read_transformed`. The derived variant is
+/// cached by the storage backend itself, so repeat requests for the same parameters
+/// don't re-decode; this handler only adds the HTTP plumbing (range, conditional GET)
+/// on top of the returned bytes.
+async fn serve_transformed(
+    state: &AppState,
+    filename: &str,
+    transform: &ReadTransform,
+    headers: &HeaderMap,
+) -> Result<Response> {
+    let data = state.storage_backend.read_transformed(filename, transform).await?;
+    let file_len = data.len() as u64;
+    let etag = format!("\"{}\"", content_integrity(&data));
+
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok())
+    {
+        if if_none_match == etag || if_none_match == "*" {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(header::ETAG, HeaderValue::from_str(&etag).unwrap())
+                .body(Body::empty())
+                .unwrap()
+                .into_response());
+        }
+    }
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, file_len));
+
+    let content_type = transform
+        .format
+        .as_deref()
+        .and_then(content_type_for_extension)
+        .unwrap_or_else(|| content_type_for(StdPath::new(filename)));
+
+    let mut response = match range {
+        Some((start, end)) => {
+            let body = data[start as usize..=end as usize].to_vec();
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, file_len),
+                )
+                .header(header::CONTENT_LENGTH, body.len().to_string())
+                .body(Body::from(body))
+                .map_err(|e| AppError::Internal(e.to_string()))?
+        }
+        None => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, file_len.to_string())
+            .body(Body::from(data))
+            .map_err(|e| AppError::Internal(e.to_string()))?,
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response_headers.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    Ok(response)
+}
+
+/// Check whether the request's validators match the current representation
+fn not_modified(headers: &HeaderMap, etag: &str, last_modified: SystemTime) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return if_none_match == etag || if_none_match == "*";
+    }
+
+    if let Some(if_modified_since) = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+    {
+        return last_modified <= if_modified_since;
+    }
+
+    false
+}
+
+fn not_modified_response(etag: &str, last_modified_http: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, HeaderValue::from_str(etag).unwrap())
+        .header(header::LAST_MODIFIED, HeaderValue::from_str(last_modified_http).unwrap())
+        .body(Body::empty())
+        .unwrap()
+        .into_response()
+}
+
+/// Parse a single-range `Range: bytes=start-end` header into an inclusive `(start, end)` pair,
+/// clamped to the file length. Multi-range requests are not supported and fall back to the
+/// full body.
+fn parse_range(header_value: &str, file_len: u64) -> Option<(u64, u64)> {
+    let spec = header_value.strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+
+    let (start_str, end_str) = spec.split_once('-')?;
+
+    if start_str.is_empty() {
+        // Suffix range: "bytes=-N" means the last N bytes
+        let suffix_len: u64 = end_str.parse().ok()?;
+        let suffix_len = suffix_len.min(file_len);
+        return Some((file_len.saturating_sub(suffix_len), file_len.saturating_sub(1)));
+    }
+
+    let start: u64 = start_str.parse().ok()?;
+    if start >= file_len {
+        return None;
+    }
+
+    let end = if end_str.is_empty() {
+        file_len.saturating_sub(1)
+    } else {
+        end_str.parse::<u64>().ok()?.min(file_len.saturating_sub(1))
+    };
+
+    if end < start {
+        return None;
+    }
+
+    Some((start, end))
+}
+
+/// Reject a requested (possibly sharded, e.g. `ab/cd/<digest>.png`) storage key that
+/// attempts path traversal or names an absolute path, before handing it to
+/// `StorageBackend::get_path`
+fn validate_image_key(filename: &str) -> Result<()> {
+    let is_traversal = filename.is_empty()
+        || filename.contains('\\')
+        || filename.starts_with('/')
+        || filename.split('/').any(|segment| segment.is_empty() || segment == "..");
+
+    if is_traversal {
+        return Err(AppError::InvalidRequest(format!("Invalid image path '{}'", filename)));
+    }
+
+    Ok(())
+}
+
+/// Guess the response content type from the file extension
+fn content_type_for(path: &StdPath) -> &'static str {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    content_type_for_extension(ext).unwrap_or("application/octet-stream")
+}
+
+/// Map an image format extension (no leading dot, case-insensitive) to its MIME type
+fn content_type_for_extension(ext: &str) -> Option<&'static str> {
+    match ext.to_lowercase().as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "avif" => Some("image/avif"),
+        "heic" => Some("image/heic"),
+        _ => None,
+    }
+}
@@ -0,0 +1,5 @@
+//! Request handlers for the public HTTP API
+
+pub mod generate;
+pub mod health;
+pub mod images;
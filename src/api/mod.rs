@@ -0,0 +1,4 @@
+//! HTTP API module - axum router construction and request handlers
+
+pub mod handlers;
+pub mod routes;
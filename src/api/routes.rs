@@ -0,0 +1,64 @@
+//! Axum router construction and middleware wiring
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use tower_http::compression::{
+    predicate::{DefaultPredicate, Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+use crate::api::handlers::{generate, health, images};
+use crate::middleware::auth::{AuthLayer, StaticKeyAuth};
+use crate::middleware::rate_limit::RateLimitLayer;
+use crate::AppState;
+
+/// Build the application's axum router, wiring compression, auth, and rate-limit
+/// middleware according to the current configuration
+pub async fn create_router(state: Arc<AppState>) -> Router {
+    let settings = state.settings.read().await;
+
+    // Health/metrics probes stay outside the compression layer: they're cheap, frequently
+    // polled, and gain nothing from gzip/brotli negotiation overhead.
+    let mut api_routes = Router::new()
+        .route("/v1/images/generations", post(generate::generate))
+        .route("/images/*filename", get(images::serve_image));
+
+    if settings.compression.enabled {
+        let predicate =
+            DefaultPredicate::new().and(SizeAbove::new(settings.compression.min_size_bytes));
+        api_routes = api_routes.layer(CompressionLayer::new().compress_when(predicate));
+    }
+
+    let health_routes = Router::new()
+        .route("/health", get(health::health))
+        .route("/metrics", get(health::metrics));
+
+    let mut router = api_routes.merge(health_routes);
+
+    if settings.rate_limit.enabled {
+        router = router.layer(
+            RateLimitLayer::with_overrides(
+                settings.rate_limit.requests_per_second,
+                settings.rate_limit.burst_size,
+                settings.auth.rate_limit_overrides.clone(),
+            )
+            .with_anonymous_quota(
+                settings.rate_limit.anonymous_requests_per_second,
+                settings.rate_limit.anonymous_burst_size,
+            ),
+        );
+    }
+
+    if settings.auth.enabled {
+        router = router.layer(AuthLayer::with_signed_image_bypass(
+            Arc::new(StaticKeyAuth::new(settings.auth.api_keys.clone())),
+            settings.auth.token_signing_key.is_some(),
+        ));
+    }
+
+    drop(settings);
+    router.with_state(state)
+}
@@ -0,0 +1,116 @@
+//! Automatic1111 Stable Diffusion WebUI shape: `POST /sdapi/v1/txt2img`, which returns
+//! bare base64 strings under `images` rather than OpenAI-style objects, and uses its own
+//! parameter names (`steps`, `cfg_scale`, `batch_size`, ...)
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    classify_error_response, classify_send_error, with_host_override, AttemptOutcome,
+    BackendAdapter,
+};
+use crate::backend::traits::{GenerateRequest, GenerateResponse, GeneratedImage};
+use crate::error::AppError;
+
+#[derive(Debug, Serialize)]
+struct Txt2ImgRequest {
+    prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    negative_prompt: Option<String>,
+    batch_size: u32,
+    width: u32,
+    height: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cfg_scale: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    steps: Option<u32>,
+}
+
+impl From<&GenerateRequest> for Txt2ImgRequest {
+    fn from(request: &GenerateRequest) -> Self {
+        Self {
+            prompt: request.prompt.clone(),
+            negative_prompt: request.negative_prompt.clone(),
+            batch_size: request.n,
+            width: request.width,
+            height: request.height,
+            seed: request.seed,
+            cfg_scale: request.guidance_scale,
+            steps: request.num_inference_steps,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Txt2ImgResponse {
+    /// Bare base64-encoded PNGs, unlike the OpenAI shape's array of objects
+    #[serde(default)]
+    images: Vec<String>,
+    /// JSON-encoded (not JSON-nested) string carrying generation metadata, including the
+    /// actual seeds used when the request left `seed` unset for random generation
+    #[serde(default)]
+    info: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Txt2ImgInfo {
+    #[serde(default)]
+    all_seeds: Vec<i64>,
+}
+
+pub struct Automatic1111Adapter;
+
+#[async_trait]
+impl BackendAdapter for Automatic1111Adapter {
+    async fn generate(
+        &self,
+        client: &Client,
+        endpoint: &str,
+        request: &GenerateRequest,
+        host_override: Option<&str>,
+    ) -> std::result::Result<GenerateResponse, AttemptOutcome> {
+        let url = format!("{}/sdapi/v1/txt2img", endpoint);
+        let api_request = Txt2ImgRequest::from(request);
+        let builder = with_host_override(client.post(&url).json(&api_request), host_override);
+
+        match builder.send().await {
+            Ok(response) if response.status().is_success() => {
+                match response.json::<Txt2ImgResponse>().await {
+                    Ok(api_response) => {
+                        let info: Txt2ImgInfo = api_response
+                            .info
+                            .as_deref()
+                            .and_then(|info| serde_json::from_str(info).ok())
+                            .unwrap_or_default();
+
+                        let images: Vec<GeneratedImage> = api_response
+                            .images
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, b64)| GeneratedImage {
+                                b64_json: Some(b64),
+                                url: None,
+                                revised_prompt: None,
+                                seed: info.all_seeds.get(i).copied().or(request.seed),
+                            })
+                            .collect();
+
+                        Ok(GenerateResponse {
+                            images,
+                            model: request.model.clone(),
+                        })
+                    }
+                    Err(e) => Err(AttemptOutcome::Terminal(AppError::BackendError(format!(
+                        "Failed to parse response: {}",
+                        e
+                    )))),
+                }
+            }
+            Ok(response) => Err(classify_error_response(response).await),
+            Err(e) => Err(classify_send_error(endpoint, e)),
+        }
+    }
+}
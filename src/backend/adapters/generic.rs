@@ -0,0 +1,75 @@
+//! Fallback adapter for backends of unknown shape
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use super::openai::{into_generate_response, ApiGenerateRequest, ApiGenerateResponse};
+use super::{
+    classify_error_response, classify_send_error, with_host_override, AttemptOutcome,
+    BackendAdapter,
+};
+use crate::backend::traits::{GenerateRequest, GenerateResponse};
+use crate::error::AppError;
+
+/// Sequentially tries a handful of path/schema combinations that common image-generation
+/// APIs use, sending the same OpenAI-shaped JSON body to each. Kept only for backends
+/// that haven't been given an explicit `api_style`; a dedicated adapter avoids both the
+/// wasted round-trips and the schema mismatch this causes against, say, an Automatic1111
+/// endpoint.
+pub struct GenericAdapter;
+
+#[async_trait]
+impl BackendAdapter for GenericAdapter {
+    async fn generate(
+        &self,
+        client: &Client,
+        endpoint: &str,
+        request: &GenerateRequest,
+        host_override: Option<&str>,
+    ) -> std::result::Result<GenerateResponse, AttemptOutcome> {
+        let api_request = ApiGenerateRequest::from(request);
+
+        // Try different endpoint patterns that common image generation APIs use
+        let urls_to_try = vec![
+            format!("{}/v1/images/generations", endpoint),
+            format!("{}/generate", endpoint),
+            format!("{}/api/generate", endpoint),
+            format!("{}/sdapi/v1/txt2img", endpoint), // Automatic1111 style
+        ];
+
+        let mut last_outcome = None;
+
+        for url in &urls_to_try {
+            let builder = with_host_override(client.post(url).json(&api_request), host_override);
+
+            match builder.send().await {
+                Ok(response) if response.status().is_success() => {
+                    match response.json::<ApiGenerateResponse>().await {
+                        Ok(api_response) => return Ok(into_generate_response(api_response)),
+                        Err(e) => {
+                            last_outcome = Some(AttemptOutcome::Terminal(AppError::BackendError(
+                                format!("Failed to parse response: {}", e),
+                            )));
+                        }
+                    }
+                }
+                Ok(response) => {
+                    last_outcome = Some(classify_error_response(response).await);
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    // Don't try other URL patterns against an endpoint we can't even
+                    // reach; let the caller retry against a different endpoint instead.
+                    return Err(classify_send_error(endpoint, e));
+                }
+                Err(e) => {
+                    last_outcome = Some(AttemptOutcome::Terminal(AppError::HttpClient(e)));
+                }
+            }
+        }
+
+        // None of the URL patterns worked
+        Err(last_outcome.unwrap_or_else(|| {
+            AttemptOutcome::Terminal(AppError::BackendError("Unknown error".to_string()))
+        }))
+    }
+}
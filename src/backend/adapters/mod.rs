@@ -0,0 +1,160 @@
+//! Backend protocol adapters
+//!
+//! An HTTP image-generation backend speaks one of a handful of request/response shapes
+//! (OpenAI's Images API, Automatic1111's Stable Diffusion WebUI, or something unknown).
+//! Each shape gets its own `BackendAdapter`, owning its request path, its serialization of
+//! `GenerateRequest`, and its decoding back into `GenerateResponse`, so `HttpBackend`
+//! itself doesn't need to know or guess which API it's talking to. A backend that hasn't
+//! been given an explicit `api_style` falls back to `GenericAdapter`, which preserves the
+//! original blind multi-path probing behavior.
+
+mod automatic1111;
+mod generic;
+mod openai;
+
+pub use automatic1111::Automatic1111Adapter;
+pub use generic::GenericAdapter;
+pub use openai::OpenAiAdapter;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use std::time::Duration;
+
+use crate::backend::traits::{GenerateRequest, GenerateResponse};
+use crate::error::AppError;
+
+/// Which request/response shape a backend's HTTP API speaks. Selects a fixed adapter
+/// that owns the request path, encoding, and decoding for that shape, instead of
+/// guessing at both the way `GenericAdapter` still does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiStyle {
+    /// OpenAI Images API shape: POSTs to `/v1/images/generations`
+    OpenAi,
+    /// Automatic1111 Stable Diffusion WebUI shape: POSTs to `/sdapi/v1/txt2img`
+    Automatic1111,
+    /// Unknown backend: sequentially try a handful of common path/shape combinations
+    Generic,
+}
+
+impl ApiStyle {
+    /// Parse the `BackendConfig.api_style` string, defaulting to `generic`
+    pub fn from_config_str(s: &str) -> Self {
+        match s {
+            "openai" => Self::OpenAi,
+            "automatic1111" => Self::Automatic1111,
+            _ => Self::Generic,
+        }
+    }
+}
+
+/// Build the adapter for a given API style
+pub fn build_adapter(style: ApiStyle) -> Box<dyn BackendAdapter> {
+    match style {
+        ApiStyle::OpenAi => Box::new(OpenAiAdapter),
+        ApiStyle::Automatic1111 => Box::new(Automatic1111Adapter),
+        ApiStyle::Generic => Box::new(GenericAdapter),
+    }
+}
+
+/// Outcome of one attempt against a single endpoint
+pub enum AttemptOutcome {
+    /// Connect/timeout failure or a 5xx/429 status; worth retrying after backing off
+    Retryable {
+        error: AppError,
+        /// Server-directed delay from a 429's `Retry-After` header or `retry_after_ms`
+        /// body field, overriding the computed exponential backoff when present
+        server_delay: Option<Duration>,
+        /// Whether this failure indicates the endpoint itself is unreachable (and should
+        /// trip its circuit breaker), as opposed to a transient or application-level
+        /// failure that says nothing about the endpoint's health
+        mark_unhealthy: bool,
+    },
+    /// Non-retryable: a 4xx status other than 429, or a response we couldn't parse
+    Terminal(AppError),
+}
+
+/// A backend protocol adapter: owns the request path, serializes a `GenerateRequest` in
+/// its backend's native shape, and decodes the response back into a `GenerateResponse`.
+/// Implementations don't touch endpoint health or retry bookkeeping directly - that's
+/// `HttpBackend`'s job based on the returned `AttemptOutcome`.
+#[async_trait]
+pub trait BackendAdapter: Send + Sync {
+    /// Send one generate request to `endpoint`, returning the parsed response or a
+    /// classified outcome describing how the caller should react to the failure
+    async fn generate(
+        &self,
+        client: &Client,
+        endpoint: &str,
+        request: &GenerateRequest,
+        host_override: Option<&str>,
+    ) -> std::result::Result<GenerateResponse, AttemptOutcome>;
+}
+
+/// Apply the configured `Host` header override to an outgoing request, if any
+fn with_host_override(
+    builder: reqwest::RequestBuilder,
+    host_override: Option<&str>,
+) -> reqwest::RequestBuilder {
+    match host_override {
+        Some(host) => builder.header(reqwest::header::HOST, host),
+        None => builder,
+    }
+}
+
+/// Classify a non-2xx response into a retry/terminal outcome, extracting any
+/// server-directed retry delay from the `Retry-After` header or a `retry_after_ms` JSON
+/// body field
+async fn classify_error_response(response: reqwest::Response) -> AttemptOutcome {
+    let status = response.status();
+    let retry_after_header = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let body = response.text().await.unwrap_or_default();
+    let retry_after_body = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v.get("retry_after_ms").and_then(|n| n.as_u64()))
+        .map(Duration::from_millis);
+    let error = AppError::BackendError(format!("Backend returned {}: {}", status, body));
+
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        AttemptOutcome::Retryable {
+            error,
+            server_delay: retry_after_header.or(retry_after_body),
+            mark_unhealthy: false,
+        }
+    } else {
+        AttemptOutcome::Terminal(error)
+    }
+}
+
+/// Classify a `send()` failure (connect/timeout/other) into a retry/terminal outcome
+fn classify_send_error(endpoint: &str, e: reqwest::Error) -> AttemptOutcome {
+    if e.is_connect() {
+        // Couldn't even establish a connection - this endpoint is down, trip its
+        // circuit breaker.
+        let error = if e.is_timeout() {
+            AppError::Timeout(format!("Connect timeout to {}", endpoint))
+        } else {
+            AppError::BackendError(format!("Connection failed to {}: {}", endpoint, e))
+        };
+        AttemptOutcome::Retryable {
+            error,
+            server_delay: None,
+            mark_unhealthy: true,
+        }
+    } else if e.is_timeout() {
+        // The connection was established fine; the backend is just slow to generate.
+        // That's not evidence the endpoint is unreachable, so leave its circuit breaker
+        // alone, but it's still worth a retry.
+        AttemptOutcome::Retryable {
+            error: AppError::Timeout(format!("Request to {} timed out", endpoint)),
+            server_delay: None,
+            mark_unhealthy: false,
+        }
+    } else {
+        AttemptOutcome::Terminal(AppError::HttpClient(e))
+    }
+}
@@ -0,0 +1,131 @@
+//! OpenAI Images API shape: `POST /v1/images/generations` with a JSON request/response
+//! body resembling `dall-e`'s
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    classify_error_response, classify_send_error, with_host_override, AttemptOutcome,
+    BackendAdapter,
+};
+use crate::backend::traits::{GenerateRequest, GenerateResponse, GeneratedImage};
+use crate::error::AppError;
+
+#[derive(Debug, Serialize)]
+pub(super) struct ApiGenerateRequest {
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub negative_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guidance_scale: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_inference_steps: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<String>,
+}
+
+impl From<&GenerateRequest> for ApiGenerateRequest {
+    fn from(request: &GenerateRequest) -> Self {
+        Self {
+            prompt: request.prompt.clone(),
+            negative_prompt: request.negative_prompt.clone(),
+            n: Some(request.n),
+            width: Some(request.width),
+            height: Some(request.height),
+            model: request.model.clone(),
+            seed: request.seed,
+            guidance_scale: request.guidance_scale,
+            num_inference_steps: request.num_inference_steps,
+            response_format: Some(request.response_format.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ApiGenerateResponse {
+    #[serde(default)]
+    pub images: Vec<ApiImageData>,
+    #[serde(default)]
+    pub data: Vec<ApiImageData>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ApiImageData {
+    #[serde(default)]
+    pub b64_json: Option<String>,
+    #[serde(default, alias = "base64")]
+    pub base64: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub revised_prompt: Option<String>,
+    #[serde(default)]
+    pub seed: Option<i64>,
+}
+
+/// Combine the `images`/`data` arrays (different OpenAI-compatible servers populate one
+/// or the other) into a `GenerateResponse`. Shared with `GenericAdapter`, whose fallback
+/// probing sends this same request shape.
+pub(super) fn into_generate_response(api_response: ApiGenerateResponse) -> GenerateResponse {
+    let mut all_images = api_response.images;
+    all_images.extend(api_response.data);
+
+    let images: Vec<GeneratedImage> = all_images
+        .into_iter()
+        .map(|img| GeneratedImage {
+            b64_json: img.b64_json.or(img.base64),
+            url: img.url,
+            revised_prompt: img.revised_prompt,
+            seed: img.seed,
+        })
+        .collect();
+
+    GenerateResponse {
+        images,
+        model: api_response.model,
+    }
+}
+
+pub struct OpenAiAdapter;
+
+#[async_trait]
+impl BackendAdapter for OpenAiAdapter {
+    async fn generate(
+        &self,
+        client: &Client,
+        endpoint: &str,
+        request: &GenerateRequest,
+        host_override: Option<&str>,
+    ) -> std::result::Result<GenerateResponse, AttemptOutcome> {
+        let url = format!("{}/v1/images/generations", endpoint);
+        let api_request = ApiGenerateRequest::from(request);
+        let builder = with_host_override(client.post(&url).json(&api_request), host_override);
+
+        match builder.send().await {
+            Ok(response) if response.status().is_success() => {
+                match response.json::<ApiGenerateResponse>().await {
+                    Ok(api_response) => Ok(into_generate_response(api_response)),
+                    Err(e) => Err(AttemptOutcome::Terminal(AppError::BackendError(format!(
+                        "Failed to parse response: {}",
+                        e
+                    )))),
+                }
+            }
+            Ok(response) => Err(classify_error_response(response).await),
+            Err(e) => Err(classify_send_error(endpoint, e)),
+        }
+    }
+}
@@ -7,12 +7,18 @@ use std::time::Duration;
 use tonic::transport::{Channel, Endpoint};
 use tracing::{debug, warn};
 
+use crate::backend::load_balancing::{build_selector, DispatchGuard, EndpointSelector, EndpointStrategy};
 use crate::backend::traits::{
-    BackendEndpoint, GenerateRequest, GenerateResponse, ImageBackend,
+    BackendEndpoint, CircuitState, GenerateRequest, GenerateResponse, ImageBackend,
 };
 use crate::config::BackendConfig;
 use crate::error::{AppError, Result};
 
+#[cfg(feature = "grpc-codegen")]
+use crate::backend::proto::grpc_health_v1::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
+
 /// gRPC-based image generation backend
 pub struct GrpcBackend {
     name: String,
@@ -21,7 +27,9 @@ pub struct GrpcBackend {
     timeout_ms: u64,
     weight: u32,
     enabled: bool,
-    current_endpoint_index: Arc<RwLock<usize>>,
+    selector: Box<dyn EndpointSelector>,
+    /// Service name passed to `grpc.health.v1.Health/Check`; empty means "overall" status
+    health_check_service: String,
 }
 
 impl GrpcBackend {
@@ -30,10 +38,20 @@ impl GrpcBackend {
         let endpoints: Vec<BackendEndpoint> = config
             .endpoints
             .iter()
-            .map(|url| BackendEndpoint::new(url.clone()))
+            .map(|spec| {
+                let (url, weight) = BackendEndpoint::parse_spec(spec);
+                BackendEndpoint::with_circuit_breaker(
+                    url,
+                    weight,
+                    config.circuit_breaker_threshold,
+                    Duration::from_secs(config.circuit_breaker_cooldown_secs),
+                )
+            })
             .collect();
 
         let channels: Vec<Option<Channel>> = vec![None; endpoints.len()];
+        let strategy = EndpointStrategy::from_config_str(&config.strategy);
+        let selector = build_selector(strategy, endpoints.len());
 
         Ok(Self {
             name: config.name.clone(),
@@ -42,7 +60,8 @@ impl GrpcBackend {
             timeout_ms: config.timeout_ms,
             weight: config.weight,
             enabled: config.enabled,
-            current_endpoint_index: Arc::new(RwLock::new(0)),
+            selector,
+            health_check_service: config.health_check_service.clone(),
         })
     }
 
@@ -86,23 +105,26 @@ impl GrpcBackend {
         Ok(channel)
     }
 
-    /// Get the next healthy endpoint index
-    fn get_next_healthy_index(&self) -> Option<usize> {
-        let endpoints = self.endpoints.read();
-        let healthy_indices: Vec<usize> = endpoints
-            .iter()
-            .enumerate()
-            .filter(|(_, e)| e.healthy)
-            .map(|(i, _)| i)
-            .collect();
+    /// Select the next available endpoint index according to the configured strategy,
+    /// honoring each endpoint's circuit breaker
+    fn get_next_endpoint(&self, affinity_key: Option<&str>) -> Option<usize> {
+        let index = {
+            let endpoints = self.endpoints.read();
+            let available: Vec<usize> = endpoints
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.is_available())
+                .map(|(i, _)| i)
+                .collect();
+
+            self.selector.select(&available, &endpoints, affinity_key)?
+        };
 
-        if healthy_indices.is_empty() {
+        let mut endpoints = self.endpoints.write();
+        if !endpoints[index].try_acquire() {
             return None;
         }
-
-        let mut index = self.current_endpoint_index.write();
-        *index = (*index + 1) % healthy_indices.len();
-        Some(healthy_indices[*index])
+        Some(index)
     }
 
     /// Mark an endpoint as unhealthy
@@ -128,6 +150,35 @@ impl GrpcBackend {
             debug!(backend = %self.name, url = %endpoint.url, "Marked gRPC endpoint as healthy");
         }
     }
+
+    /// Call the standard `grpc.health.v1.Health/Check` RPC and treat only a `SERVING`
+    /// response as healthy; `NOT_SERVING`, `UNKNOWN`, and RPC errors are unhealthy
+    #[cfg(feature = "grpc-codegen")]
+    async fn check_serving(&self, channel: Channel) -> bool {
+        let mut client = HealthClient::new(channel);
+        let request = tonic::Request::new(HealthCheckRequest {
+            service: self.health_check_service.clone(),
+        });
+
+        match client.check(request).await {
+            Ok(response) => response.into_inner().status == ServingStatus::Serving as i32,
+            Err(status) => {
+                debug!(
+                    backend = %self.name,
+                    error = %status,
+                    "grpc.health.v1.Health/Check failed"
+                );
+                false
+            }
+        }
+    }
+
+    /// Without the `grpc-codegen` feature (no `protoc` available at build time) we can't
+    /// speak the health protocol, so fall back to the previous connectivity-only heuristic
+    #[cfg(not(feature = "grpc-codegen"))]
+    async fn check_serving(&self, _channel: Channel) -> bool {
+        true
+    }
 }
 
 #[async_trait]
@@ -144,10 +195,15 @@ impl ImageBackend for GrpcBackend {
         self.endpoints.read().iter().map(|e| e.url.clone()).collect()
     }
 
-    async fn generate(&self, _request: GenerateRequest) -> Result<GenerateResponse> {
+    fn circuit_states(&self) -> Vec<CircuitState> {
+        self.endpoints.read().iter().map(|e| e.circuit_state).collect()
+    }
+
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
         let index = self
-            .get_next_healthy_index()
+            .get_next_endpoint(request.model.as_deref())
             .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
+        let _dispatch_guard = DispatchGuard::new(self.selector.as_ref(), index);
 
         let _channel = self.get_channel(index).await?;
 
@@ -196,27 +252,26 @@ impl ImageBackend for GrpcBackend {
         let mut any_healthy = false;
 
         for index in 0..endpoints_len {
-            match self.get_channel(index).await {
-                Ok(_channel) => {
-                    // TODO: Make actual gRPC health check call
-                    // For now, just check if we can connect
-                    self.mark_endpoint_healthy(index);
-                    any_healthy = true;
-                    debug!(
-                        backend = %self.name,
-                        index = index,
-                        "gRPC health check passed (connection test)"
-                    );
-                }
+            let serving = match self.get_channel(index).await {
+                Ok(channel) => self.check_serving(channel).await,
                 Err(e) => {
-                    self.mark_endpoint_unhealthy(index);
                     debug!(
                         backend = %self.name,
                         index = index,
                         error = %e,
-                        "gRPC health check failed"
+                        "gRPC health check failed to connect"
                     );
+                    false
                 }
+            };
+
+            if serving {
+                self.mark_endpoint_healthy(index);
+                any_healthy = true;
+                debug!(backend = %self.name, index = index, "gRPC health check passed (SERVING)");
+            } else {
+                self.mark_endpoint_unhealthy(index);
+                debug!(backend = %self.name, index = index, "gRPC health check failed (not SERVING)");
             }
         }
 
@@ -2,18 +2,83 @@
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
+use rand::Rng;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, warn};
 
+use crate::backend::adapters::{build_adapter, ApiStyle, AttemptOutcome, BackendAdapter};
+use crate::backend::load_balancing::{
+    build_selector, DispatchGuard, EndpointSelector, EndpointStrategy,
+};
 use crate::backend::traits::{
-    BackendEndpoint, GenerateRequest, GenerateResponse, GeneratedImage, ImageBackend,
+    BackendEndpoint, CircuitState, GenerateRequest, GenerateResponse, ImageBackend,
 };
-use crate::config::BackendConfig;
+use crate::config::{BackendConfig, RetryConfig};
 use crate::error::{AppError, Result};
 
+/// Load the `reqwest::Client` TLS options (custom root CA, client identity, invalid-cert
+/// escape hatch) from a backend's `TlsConfig`, mapping any cert/key load failure to
+/// `AppError::Config` since it's a deployment misconfiguration, not a runtime backend
+/// error. `sni_override` isn't applied here; it's a per-request `Host` header set by the
+/// caller, since `reqwest`'s stable builder has no raw SNI override.
+fn apply_tls_config(
+    mut builder: reqwest::ClientBuilder,
+    tls: &crate::config::TlsConfig,
+) -> Result<reqwest::ClientBuilder> {
+    if let Some(ca_cert_path) = &tls.ca_cert_path {
+        let pem = std::fs::read(ca_cert_path).map_err(|e| {
+            AppError::Config(config::ConfigError::Message(format!(
+                "Failed to read ca_cert_path '{}': {}",
+                ca_cert_path, e
+            )))
+        })?;
+        let cert = reqwest::Certificate::from_pem(&pem).map_err(|e| {
+            AppError::Config(config::ConfigError::Message(format!(
+                "Invalid CA certificate at '{}': {}",
+                ca_cert_path, e
+            )))
+        })?;
+        builder = builder.add_root_certificate(cert);
+    }
+
+    if let Some(client_cert_path) = &tls.client_cert_path {
+        let client_key_path = tls.client_key_path.as_ref().ok_or_else(|| {
+            AppError::Config(config::ConfigError::Message(
+                "client_cert_path is set but client_key_path is missing".to_string(),
+            ))
+        })?;
+        let mut identity_pem = std::fs::read(client_cert_path).map_err(|e| {
+            AppError::Config(config::ConfigError::Message(format!(
+                "Failed to read client_cert_path '{}': {}",
+                client_cert_path, e
+            )))
+        })?;
+        let mut key_pem = std::fs::read(client_key_path).map_err(|e| {
+            AppError::Config(config::ConfigError::Message(format!(
+                "Failed to read client_key_path '{}': {}",
+                client_key_path, e
+            )))
+        })?;
+        identity_pem.append(&mut key_pem);
+        let identity = reqwest::Identity::from_pem(&identity_pem).map_err(|e| {
+            AppError::Config(config::ConfigError::Message(format!(
+                "Invalid client identity from '{}' / '{}': {}",
+                client_cert_path, client_key_path, e
+            )))
+        })?;
+        builder = builder.identity(identity);
+    }
+
+    if tls.accept_invalid_certs {
+        warn!("accept_invalid_certs is enabled; TLS certificate validation is disabled for this backend");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    Ok(builder)
+}
+
 /// HTTP-based image generation backend
 pub struct HttpBackend {
     name: String,
@@ -22,72 +87,59 @@ pub struct HttpBackend {
     health_check_path: String,
     weight: u32,
     enabled: bool,
-    current_endpoint_index: Arc<RwLock<usize>>,
-}
-
-/// Generic API request for HTTP backends
-#[derive(Debug, Serialize)]
-struct ApiGenerateRequest {
-    prompt: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    negative_prompt: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    n: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    width: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    height: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    model: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    seed: Option<i64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    guidance_scale: Option<f32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    num_inference_steps: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    response_format: Option<String>,
-}
-
-/// Generic API response from HTTP backends
-#[derive(Debug, Deserialize)]
-struct ApiGenerateResponse {
-    #[serde(default)]
-    images: Vec<ApiImageData>,
-    #[serde(default)]
-    data: Vec<ApiImageData>,
-    #[serde(default)]
-    model: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ApiImageData {
-    #[serde(default)]
-    b64_json: Option<String>,
-    #[serde(default, alias = "base64")]
-    base64: Option<String>,
-    #[serde(default)]
-    url: Option<String>,
-    #[serde(default)]
-    revised_prompt: Option<String>,
-    #[serde(default)]
-    seed: Option<i64>,
+    selector: Box<dyn EndpointSelector>,
+    retry: RetryConfig,
+    /// Hostname to send as the `Host` header on every request, overriding the one
+    /// implied by the endpoint URL. Reqwest's stable API doesn't expose the raw TLS SNI
+    /// field, so this is the practical equivalent: it's what most TLS-terminating
+    /// proxies route and verify certificates against.
+    sni_override: Option<String>,
+    /// Request/response adapter for this backend's configured `api_style`
+    adapter: Box<dyn BackendAdapter>,
 }
 
 impl HttpBackend {
     /// Create a new HTTP backend from configuration
     pub fn new(config: &BackendConfig) -> Result<Self> {
-        let client = Client::builder()
+        let transport = &config.transport;
+
+        if transport.tcp_fast_open {
+            debug!(
+                backend = %config.name,
+                "tcp_fast_open is configured but not yet supported by the HTTP client; ignoring"
+            );
+        }
+
+        let mut client_builder = Client::builder()
             .timeout(Duration::from_millis(config.timeout_ms))
+            .connect_timeout(Duration::from_millis(transport.connect_timeout_ms))
+            .pool_max_idle_per_host(transport.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(transport.pool_idle_timeout_secs))
+            .tcp_keepalive(Duration::from_secs(transport.tcp_keepalive_secs))
+            .tcp_nodelay(transport.tcp_nodelay);
+        client_builder = apply_tls_config(client_builder, &config.tls)?;
+
+        let client = client_builder
             .build()
             .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
         let endpoints: Vec<BackendEndpoint> = config
             .endpoints
             .iter()
-            .map(|url| BackendEndpoint::new(url.clone()))
+            .map(|spec| {
+                let (url, weight) = BackendEndpoint::parse_spec(spec);
+                BackendEndpoint::with_circuit_breaker(
+                    url,
+                    weight,
+                    config.circuit_breaker_threshold,
+                    Duration::from_secs(config.circuit_breaker_cooldown_secs),
+                )
+            })
             .collect();
 
+        let strategy = EndpointStrategy::from_config_str(&config.strategy);
+        let selector = build_selector(strategy, endpoints.len());
+
         Ok(Self {
             name: config.name.clone(),
             client,
@@ -95,25 +147,33 @@ impl HttpBackend {
             health_check_path: config.health_check_path.clone(),
             weight: config.weight,
             enabled: config.enabled,
-            current_endpoint_index: Arc::new(RwLock::new(0)),
+            selector,
+            retry: config.retry.clone(),
+            sni_override: config.tls.sni_override.clone(),
+            adapter: build_adapter(ApiStyle::from_config_str(&config.api_style)),
         })
     }
 
-    /// Get the next healthy endpoint using round-robin
-    fn get_next_endpoint(&self) -> Option<String> {
-        let endpoints = self.endpoints.read();
-        let healthy_endpoints: Vec<_> = endpoints
-            .iter()
-            .filter(|e| e.healthy)
-            .collect();
+    /// Select the next available endpoint according to the configured strategy, honoring
+    /// each endpoint's circuit breaker
+    fn get_next_endpoint(&self, affinity_key: Option<&str>) -> Option<(usize, String)> {
+        let index = {
+            let endpoints = self.endpoints.read();
+            let available: Vec<usize> = endpoints
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.is_available())
+                .map(|(i, _)| i)
+                .collect();
+
+            self.selector.select(&available, &endpoints, affinity_key)?
+        };
 
-        if healthy_endpoints.is_empty() {
+        let mut endpoints = self.endpoints.write();
+        if !endpoints[index].try_acquire() {
             return None;
         }
-
-        let mut index = self.current_endpoint_index.write();
-        *index = (*index + 1) % healthy_endpoints.len();
-        Some(healthy_endpoints[*index].url.clone())
+        Some((index, endpoints[index].url.clone()))
     }
 
     /// Mark an endpoint as unhealthy
@@ -135,6 +195,16 @@ impl HttpBackend {
     }
 }
 
+/// Full-jitter exponential backoff: a random duration in `[0, min(max_delay, base_delay *
+/// 2^attempt))`, per the approach popularized by AWS's retry guidance
+fn full_jitter_backoff(attempt: u32, base_delay_ms: u64, max_delay_ms: u64) -> Duration {
+    let window_ms = base_delay_ms
+        .saturating_mul(1u64 << attempt.min(32))
+        .min(max_delay_ms.max(1));
+    let jittered_ms = rand::thread_rng().gen_range(0..window_ms.max(1));
+    Duration::from_millis(jittered_ms)
+}
+
 #[async_trait]
 impl ImageBackend for HttpBackend {
     fn name(&self) -> &str {
@@ -146,104 +216,83 @@ impl ImageBackend for HttpBackend {
     }
 
     fn endpoints(&self) -> Vec<String> {
-        self.endpoints.read().iter().map(|e| e.url.clone()).collect()
+        self.endpoints
+            .read()
+            .iter()
+            .map(|e| e.url.clone())
+            .collect()
     }
 
-    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
-        let endpoint = self
-            .get_next_endpoint()
-            .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
-
-        debug!(backend = %self.name, endpoint = %endpoint, "Sending generate request");
-
-        let api_request = ApiGenerateRequest {
-            prompt: request.prompt,
-            negative_prompt: request.negative_prompt,
-            n: Some(request.n),
-            width: Some(request.width),
-            height: Some(request.height),
-            model: request.model,
-            seed: request.seed,
-            guidance_scale: request.guidance_scale,
-            num_inference_steps: request.num_inference_steps,
-            response_format: Some(request.response_format),
-        };
+    fn circuit_states(&self) -> Vec<CircuitState> {
+        self.endpoints
+            .read()
+            .iter()
+            .map(|e| e.circuit_state)
+            .collect()
+    }
 
-        // Try different endpoint patterns that common image generation APIs use
-        let urls_to_try = vec![
-            format!("{}/v1/images/generations", endpoint),
-            format!("{}/generate", endpoint),
-            format!("{}/api/generate", endpoint),
-            format!("{}/sdapi/v1/txt2img", endpoint), // Automatic1111 style
-        ];
+    async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse> {
+        let affinity_key = request.model.clone();
 
+        let max_attempts = self.retry.max_attempts.max(1);
         let mut last_error = None;
 
-        for url in &urls_to_try {
+        for attempt in 0..max_attempts {
+            let (index, endpoint) = self
+                .get_next_endpoint(affinity_key.as_deref())
+                .ok_or_else(|| AppError::NoHealthyBackends(self.name.clone()))?;
+            let _dispatch_guard = DispatchGuard::new(self.selector.as_ref(), index);
+
+            debug!(backend = %self.name, endpoint = %endpoint, attempt, "Sending generate request");
+
             match self
-                .client
-                .post(url)
-                .json(&api_request)
-                .send()
+                .adapter
+                .generate(
+                    &self.client,
+                    &endpoint,
+                    &request,
+                    self.sni_override.as_deref(),
+                )
                 .await
             {
                 Ok(response) => {
-                    if response.status().is_success() {
-                        match response.json::<ApiGenerateResponse>().await {
-                            Ok(api_response) => {
-                                self.mark_endpoint_healthy(&endpoint);
-                                
-                                // Combine images from both possible response formats
-                                let mut all_images = api_response.images;
-                                all_images.extend(api_response.data);
-                                
-                                let images: Vec<GeneratedImage> = all_images
-                                    .into_iter()
-                                    .map(|img| GeneratedImage {
-                                        b64_json: img.b64_json.or(img.base64),
-                                        url: img.url,
-                                        revised_prompt: img.revised_prompt,
-                                        seed: img.seed,
-                                    })
-                                    .collect();
-
-                                return Ok(GenerateResponse {
-                                    images,
-                                    model: api_response.model,
-                                });
-                            }
-                            Err(e) => {
-                                last_error = Some(AppError::BackendError(format!(
-                                    "Failed to parse response: {}",
-                                    e
-                                )));
-                            }
-                        }
-                    } else {
-                        let status = response.status();
-                        let body = response.text().await.unwrap_or_default();
-                        last_error = Some(AppError::BackendError(format!(
-                            "Backend returned {}: {}",
-                            status, body
-                        )));
-                    }
-                }
-                Err(e) if e.is_connect() || e.is_timeout() => {
-                    // Connection or timeout error - don't try other URL patterns
-                    self.mark_endpoint_unhealthy(&endpoint);
-                    return Err(AppError::BackendError(format!(
-                        "Connection failed to {}: {}",
-                        endpoint, e
-                    )));
+                    self.mark_endpoint_healthy(&endpoint);
+                    return Ok(response);
                 }
-                Err(e) => {
-                    last_error = Some(AppError::HttpClient(e));
+                Err(AttemptOutcome::Terminal(error)) => return Err(error),
+                Err(AttemptOutcome::Retryable {
+                    error,
+                    server_delay,
+                    mark_unhealthy,
+                }) => {
+                    if mark_unhealthy {
+                        self.mark_endpoint_unhealthy(&endpoint);
+                    }
+                    if attempt + 1 >= max_attempts {
+                        last_error = Some(error);
+                        break;
+                    }
+                    let delay = server_delay.unwrap_or_else(|| {
+                        full_jitter_backoff(
+                            attempt,
+                            self.retry.base_delay_ms,
+                            self.retry.max_delay_ms,
+                        )
+                    });
+                    warn!(
+                        backend = %self.name,
+                        endpoint = %endpoint,
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %error,
+                        "Retrying backend request after backoff"
+                    );
+                    last_error = Some(error);
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
 
-        // If we get here, none of the URL patterns worked
-        self.mark_endpoint_unhealthy(&endpoint);
         Err(last_error.unwrap_or_else(|| AppError::BackendError("Unknown error".to_string())))
     }
 
@@ -253,8 +302,12 @@ impl ImageBackend for HttpBackend {
 
         for endpoint in &endpoints {
             let url = format!("{}{}", endpoint.url, self.health_check_path);
-            
-            match self.client.get(&url).send().await {
+            let mut request = self.client.get(&url);
+            if let Some(sni_override) = &self.sni_override {
+                request = request.header(reqwest::header::HOST, sni_override);
+            }
+
+            match request.send().await {
                 Ok(response) if response.status().is_success() => {
                     self.mark_endpoint_healthy(&endpoint.url);
                     any_healthy = true;
@@ -295,5 +348,10 @@ impl ImageBackend for HttpBackend {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
-}
 
+    fn supports_batching(&self) -> bool {
+        // Every adapter's request shape already carries an `n` (or `batch_size`) field,
+        // so one HTTP call can serve several same-prompt requests at once.
+        true
+    }
+}
@@ -0,0 +1,248 @@
+//! Pluggable strategies for selecting among a single backend's own endpoints
+//!
+//! This is distinct from `gateway::load_balancer`, which picks among different
+//! *backends*; this module picks among the endpoints that make up one backend.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::backend::traits::BackendEndpoint;
+
+/// Number of virtual nodes placed on the consistent-hash ring per endpoint
+const VIRTUAL_NODES_PER_ENDPOINT: usize = 160;
+
+/// Per-backend endpoint selection strategy, configured via `BackendConfig.strategy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointStrategy {
+    RoundRobin,
+    Weighted,
+    LeastConnections,
+    ConsistentHash,
+}
+
+impl EndpointStrategy {
+    /// Parse the `BackendConfig.strategy` string, defaulting to round-robin
+    pub fn from_config_str(s: &str) -> Self {
+        match s {
+            "weighted" => Self::Weighted,
+            "least_conn" => Self::LeastConnections,
+            "consistent_hash" => Self::ConsistentHash,
+            _ => Self::RoundRobin,
+        }
+    }
+}
+
+/// Selects among a backend's healthy endpoints, tracking whatever per-endpoint state
+/// its strategy needs (in-flight counts, hash ring position, ...)
+pub trait EndpointSelector: Send + Sync {
+    /// Pick an index into `endpoints` from the `healthy` subset, optionally informed by
+    /// an affinity key (e.g. the request's model name or a client-supplied header)
+    fn select(
+        &self,
+        healthy: &[usize],
+        endpoints: &[BackendEndpoint],
+        affinity_key: Option<&str>,
+    ) -> Option<usize>;
+
+    /// Called when a request is dispatched to `index`, for connection-tracking strategies
+    fn on_dispatch(&self, _index: usize) {}
+
+    /// Called when a request to `index` completes, for connection-tracking strategies
+    fn on_complete(&self, _index: usize) {}
+}
+
+/// Simple round-robin over the healthy set
+#[derive(Default)]
+pub struct RoundRobinSelector {
+    counter: AtomicUsize,
+}
+
+impl RoundRobinSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EndpointSelector for RoundRobinSelector {
+    fn select(&self, healthy: &[usize], _endpoints: &[BackendEndpoint], _affinity_key: Option<&str>) -> Option<usize> {
+        if healthy.is_empty() {
+            return None;
+        }
+        let i = self.counter.fetch_add(1, Ordering::Relaxed) % healthy.len();
+        Some(healthy[i])
+    }
+}
+
+struct WeightedState {
+    current_index: usize,
+    current_weight: i64,
+}
+
+/// Interleaved (gcd-stepped) weighted round-robin, distributing proportionally to each
+/// endpoint's weight
+pub struct WeightedSelector {
+    state: Mutex<WeightedState>,
+}
+
+impl WeightedSelector {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(WeightedState {
+                current_index: 0,
+                current_weight: 0,
+            }),
+        }
+    }
+}
+
+impl EndpointSelector for WeightedSelector {
+    fn select(&self, healthy: &[usize], endpoints: &[BackendEndpoint], _affinity_key: Option<&str>) -> Option<usize> {
+        if healthy.is_empty() {
+            return None;
+        }
+        if healthy.len() == 1 {
+            return Some(healthy[0]);
+        }
+
+        let weights: Vec<i64> = healthy
+            .iter()
+            .map(|&i| endpoints[i].weight.max(1) as i64)
+            .collect();
+        let max_weight = *weights.iter().max().unwrap_or(&1);
+        let gcd = weights.iter().copied().fold(0, gcd_i64);
+
+        let mut state = self.state.lock().unwrap();
+        loop {
+            state.current_index = (state.current_index + 1) % healthy.len();
+            if state.current_index == 0 {
+                state.current_weight -= gcd;
+                if state.current_weight <= 0 {
+                    state.current_weight = max_weight;
+                }
+            }
+            if weights[state.current_index] >= state.current_weight {
+                return Some(healthy[state.current_index]);
+            }
+        }
+    }
+}
+
+fn gcd_i64(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd_i64(b, a % b)
+    }
+}
+
+/// Picks the endpoint with the fewest in-flight requests, tracked via atomic counters
+pub struct LeastConnectionsSelector {
+    in_flight: Vec<AtomicUsize>,
+}
+
+impl LeastConnectionsSelector {
+    pub fn new(endpoint_count: usize) -> Self {
+        Self {
+            in_flight: (0..endpoint_count).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+}
+
+impl EndpointSelector for LeastConnectionsSelector {
+    fn select(&self, healthy: &[usize], _endpoints: &[BackendEndpoint], _affinity_key: Option<&str>) -> Option<usize> {
+        healthy
+            .iter()
+            .copied()
+            .min_by_key(|&i| self.in_flight.get(i).map(|c| c.load(Ordering::Relaxed)).unwrap_or(0))
+    }
+
+    fn on_dispatch(&self, index: usize) {
+        if let Some(counter) = self.in_flight.get(index) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn on_complete(&self, index: usize) {
+        if let Some(counter) = self.in_flight.get(index) {
+            counter.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Consistent-hash ring over endpoint indices, so the same affinity key (typically the
+/// requested model) routes to the same warm endpoint. Removing an endpoint only remaps
+/// the keys that fell on its arc of the ring.
+pub struct ConsistentHashSelector {
+    ring: BTreeMap<u64, usize>,
+}
+
+impl ConsistentHashSelector {
+    pub fn new(endpoint_count: usize) -> Self {
+        let mut ring = BTreeMap::new();
+        for index in 0..endpoint_count {
+            for vnode in 0..VIRTUAL_NODES_PER_ENDPOINT {
+                let hash = hash_key(&format!("{}-{}", index, vnode));
+                ring.insert(hash, index);
+            }
+        }
+        Self { ring }
+    }
+}
+
+impl EndpointSelector for ConsistentHashSelector {
+    fn select(&self, healthy: &[usize], _endpoints: &[BackendEndpoint], affinity_key: Option<&str>) -> Option<usize> {
+        if healthy.is_empty() {
+            return None;
+        }
+
+        let key = affinity_key.unwrap_or("default");
+        let hash = hash_key(key);
+        let healthy_set: HashSet<usize> = healthy.iter().copied().collect();
+
+        self.ring
+            .range(hash..)
+            .chain(self.ring.iter())
+            .map(|(_, &index)| index)
+            .find(|index| healthy_set.contains(index))
+    }
+}
+
+fn hash_key(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// RAII guard that reports a dispatch to a selector on construction and its completion
+/// on drop, so connection-tracking strategies stay accurate regardless of how the
+/// in-flight request returns
+pub struct DispatchGuard<'a> {
+    selector: &'a dyn EndpointSelector,
+    index: usize,
+}
+
+impl<'a> DispatchGuard<'a> {
+    pub fn new(selector: &'a dyn EndpointSelector, index: usize) -> Self {
+        selector.on_dispatch(index);
+        Self { selector, index }
+    }
+}
+
+impl Drop for DispatchGuard<'_> {
+    fn drop(&mut self) {
+        self.selector.on_complete(self.index);
+    }
+}
+
+/// Build the endpoint selector configured for a backend
+pub fn build_selector(strategy: EndpointStrategy, endpoint_count: usize) -> Box<dyn EndpointSelector> {
+    match strategy {
+        EndpointStrategy::RoundRobin => Box::new(RoundRobinSelector::new()),
+        EndpointStrategy::Weighted => Box::new(WeightedSelector::new()),
+        EndpointStrategy::LeastConnections => Box::new(LeastConnectionsSelector::new(endpoint_count)),
+        EndpointStrategy::ConsistentHash => Box::new(ConsistentHashSelector::new(endpoint_count)),
+    }
+}
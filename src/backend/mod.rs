@@ -1,7 +1,9 @@
 //! Backend module - Traits, HTTP/gRPC clients, and registry
 
+pub mod adapters;
 pub mod grpc_backend;
 pub mod http_backend;
+pub mod load_balancing;
 pub mod proto;
 pub mod registry;
 pub mod traits;
@@ -0,0 +1,10 @@
+//! Generated protobuf/gRPC client code
+//!
+//! Compiled from `proto/*.proto` by `build.rs` when the `grpc-codegen` feature is enabled
+//! (requires `protoc` on `PATH`). Without that feature these modules are simply absent, and
+//! callers fall back to connectivity-only checks.
+
+#[cfg(feature = "grpc-codegen")]
+pub mod grpc_health_v1 {
+    include!("grpc.health.v1.rs");
+}
@@ -2,6 +2,7 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 use crate::error::Result;
 
@@ -10,31 +11,31 @@ use crate::error::Result;
 pub struct GenerateRequest {
     /// The prompt to generate images from
     pub prompt: String,
-    
+
     /// Negative prompt (things to avoid)
     pub negative_prompt: Option<String>,
-    
+
     /// Number of images to generate
     pub n: u32,
-    
+
     /// Image width
     pub width: u32,
-    
+
     /// Image height
     pub height: u32,
-    
+
     /// Model identifier
     pub model: Option<String>,
-    
+
     /// Random seed for reproducibility
     pub seed: Option<i64>,
-    
+
     /// Guidance scale / CFG scale
     pub guidance_scale: Option<f32>,
-    
+
     /// Number of inference steps
     pub num_inference_steps: Option<u32>,
-    
+
     /// Response format: "b64_json", "url", or "file"
     pub response_format: String,
 }
@@ -44,13 +45,13 @@ pub struct GenerateRequest {
 pub struct GeneratedImage {
     /// Base64 encoded image data
     pub b64_json: Option<String>,
-    
+
     /// URL to the image
     pub url: Option<String>,
-    
+
     /// Revised prompt if the model modified it
     pub revised_prompt: Option<String>,
-    
+
     /// Seed used for generation
     pub seed: Option<i64>,
 }
@@ -60,7 +61,7 @@ pub struct GeneratedImage {
 pub struct GenerateResponse {
     /// List of generated images
     pub images: Vec<GeneratedImage>,
-    
+
     /// Model used for generation
     pub model: Option<String>,
 }
@@ -74,6 +75,12 @@ pub struct BackendStatus {
     pub healthy: bool,
     pub weight: u32,
     pub enabled: bool,
+    /// Circuit breaker state of each endpoint, in the same order as `endpoints`
+    pub circuit_states: Vec<CircuitState>,
+    /// Current in-flight request count, as tracked by the `LoadBalancer` that selects
+    /// among backends (always `0` from `ImageBackend::status()`'s own default impl;
+    /// populated via `LoadBalancer::backend_status`)
+    pub in_flight: u64,
 }
 
 /// Trait for image generation backends
@@ -81,69 +88,219 @@ pub struct BackendStatus {
 pub trait ImageBackend: Send + Sync {
     /// Get the backend name
     fn name(&self) -> &str;
-    
+
     /// Get the backend protocol (http or grpc)
     fn protocol(&self) -> &str;
-    
+
     /// Get the list of endpoints
     fn endpoints(&self) -> Vec<String>;
-    
+
+    /// Circuit breaker state of each endpoint, in the same order as `endpoints()`
+    fn circuit_states(&self) -> Vec<CircuitState> {
+        Vec::new()
+    }
+
     /// Generate images from a request
     async fn generate(&self, request: GenerateRequest) -> Result<GenerateResponse>;
-    
+
+    /// Generate images for a batch of compatible requests, one result per input request
+    /// in the same order. The default simply dispatches each request individually;
+    /// backends that support a true batched forward pass should override this.
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerateRequest>,
+    ) -> Vec<Result<GenerateResponse>> {
+        let mut results = Vec::with_capacity(requests.len());
+        for request in requests {
+            results.push(self.generate(request).await);
+        }
+        results
+    }
+
+    /// Whether this backend can genuinely serve several requests in one round-trip (e.g.
+    /// generating `n` images from a single prompt in a single call), as opposed to
+    /// `generate_batch`'s default one-by-one dispatch. The continuous-batching processor
+    /// uses this to decide whether same-prompt requests sharing a bucket should be merged
+    /// into one combined `generate` call instead of falling back to `generate_batch`.
+    fn supports_batching(&self) -> bool {
+        false
+    }
+
     /// Check if the backend is healthy
     async fn health_check(&self) -> bool;
-    
+
     /// Get the backend weight for load balancing
     fn weight(&self) -> u32;
-    
+
     /// Check if the backend is enabled
     fn is_enabled(&self) -> bool;
-    
+
     /// Get current status
     fn status(&self) -> BackendStatus {
+        let circuit_states = self.circuit_states();
+        let healthy =
+            circuit_states.is_empty() || circuit_states.iter().any(|s| *s != CircuitState::Open);
         BackendStatus {
             name: self.name().to_string(),
             protocol: self.protocol().to_string(),
             endpoints: self.endpoints(),
-            healthy: true, // Will be updated by health check
+            healthy,
             weight: self.weight(),
             enabled: self.is_enabled(),
+            circuit_states,
+            in_flight: 0,
         }
     }
 }
 
-/// Backend endpoint with health status
+/// Circuit breaker state for a single backend endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Normal operation; failures are simply counted
+    Closed,
+    /// Tripped; the endpoint is skipped until the cooldown elapses
+    Open,
+    /// Cooldown elapsed; exactly one trial request is in flight
+    HalfOpen,
+}
+
+/// Maximum multiple of the base cooldown that exponential backoff can reach
+const MAX_COOLDOWN_MULTIPLIER: u32 = 16;
+
+/// Backend endpoint with circuit-breaker health tracking
 #[derive(Debug, Clone)]
 pub struct BackendEndpoint {
     pub url: String,
+    /// Convenience flag for callers that just want a yes/no answer without reasoning
+    /// about the breaker: false from the moment the breaker trips until it closes again,
+    /// including while a `HalfOpen` trial is running
     pub healthy: bool,
-    pub last_check: Option<std::time::Instant>,
+    pub last_check: Option<Instant>,
     pub consecutive_failures: u32,
+    /// Relative weight for the `weighted` load-balancing strategy
+    pub weight: u32,
+    pub circuit_state: CircuitState,
+    /// When the breaker last tripped to `Open`
+    pub opened_at: Option<Instant>,
+    /// Number of consecutive trips, used to back off the cooldown exponentially
+    consecutive_opens: u32,
+    failure_threshold: u32,
+    base_cooldown: Duration,
 }
 
 impl BackendEndpoint {
     pub fn new(url: String) -> Self {
+        Self::with_weight(url, 1)
+    }
+
+    pub fn with_weight(url: String, weight: u32) -> Self {
+        Self::with_circuit_breaker(url, weight, 3, Duration::from_secs(30))
+    }
+
+    pub fn with_circuit_breaker(
+        url: String,
+        weight: u32,
+        failure_threshold: u32,
+        cooldown: Duration,
+    ) -> Self {
         Self {
             url,
             healthy: true, // Assume healthy until proven otherwise
             last_check: None,
             consecutive_failures: 0,
+            weight: weight.max(1),
+            circuit_state: CircuitState::Closed,
+            opened_at: None,
+            consecutive_opens: 0,
+            failure_threshold: failure_threshold.max(1),
+            base_cooldown: cooldown,
         }
     }
-    
+
+    /// Parse a `url` or `url@weight` endpoint spec from configuration
+    pub fn parse_spec(spec: &str) -> (String, u32) {
+        match spec.rsplit_once('@') {
+            Some((url, weight))
+                if weight.chars().all(|c| c.is_ascii_digit()) && !weight.is_empty() =>
+            {
+                (url.to_string(), weight.parse().unwrap_or(1))
+            }
+            _ => (spec.to_string(), 1),
+        }
+    }
+
+    /// Record a successful request. Closes the breaker (from `Closed` or a winning
+    /// `HalfOpen` trial) and resets the failure streak.
     pub fn mark_healthy(&mut self) {
-        self.healthy = true;
-        self.last_check = Some(std::time::Instant::now());
+        self.last_check = Some(Instant::now());
         self.consecutive_failures = 0;
+        self.consecutive_opens = 0;
+        self.circuit_state = CircuitState::Closed;
+        self.opened_at = None;
+        self.healthy = true;
     }
-    
+
+    /// Record a failed request. In `Closed`, trips the breaker once `failure_threshold`
+    /// consecutive failures accrue. A failed `HalfOpen` trial reopens it immediately with
+    /// a backed-off cooldown.
     pub fn mark_unhealthy(&mut self) {
+        self.last_check = Some(Instant::now());
         self.consecutive_failures += 1;
-        if self.consecutive_failures >= 3 {
-            self.healthy = false;
+
+        match self.circuit_state {
+            CircuitState::Closed => {
+                if self.consecutive_failures >= self.failure_threshold {
+                    self.trip();
+                }
+            }
+            CircuitState::HalfOpen => self.trip(),
+            CircuitState::Open => {}
         }
-        self.last_check = Some(std::time::Instant::now());
+
+        self.healthy = self.circuit_state != CircuitState::Open;
     }
-}
 
+    /// Whether this endpoint may currently receive traffic, without mutating state
+    pub fn is_available(&self) -> bool {
+        match self.circuit_state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => false, // a trial request is already in flight
+            CircuitState::Open => self
+                .opened_at
+                .map(|opened| opened.elapsed() >= self.cooldown())
+                .unwrap_or(true),
+        }
+    }
+
+    /// If `Open` and the cooldown has elapsed, atomically transition to `HalfOpen` and
+    /// grant the caller the sole trial request. Returns whether the endpoint may be used
+    /// right now; callers must treat `false` as "not available this round".
+    pub fn try_acquire(&mut self) -> bool {
+        match self.circuit_state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => false,
+            CircuitState::Open => {
+                let elapsed = self.opened_at.map(|o| o.elapsed()).unwrap_or(Duration::MAX);
+                if elapsed >= self.cooldown() {
+                    self.circuit_state = CircuitState::HalfOpen;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn trip(&mut self) {
+        self.circuit_state = CircuitState::Open;
+        self.opened_at = Some(Instant::now());
+        self.consecutive_opens += 1;
+    }
+
+    /// Cooldown before the next trial, doubling with each consecutive trip up to
+    /// `MAX_COOLDOWN_MULTIPLIER` times the base cooldown
+    fn cooldown(&self) -> Duration {
+        let multiplier = 1u32 << self.consecutive_opens.saturating_sub(1).min(4);
+        self.base_cooldown * multiplier.min(MAX_COOLDOWN_MULTIPLIER)
+    }
+}
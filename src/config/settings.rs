@@ -2,6 +2,7 @@
 
 use crate::error::{AppError, Result};
 use config::{Config, Environment, File};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -15,6 +16,15 @@ pub struct Settings {
     pub logging: LoggingConfig,
     #[serde(default)]
     pub backends: Vec<BackendConfig>,
+    /// Global gateway filter pipeline, run for every request whichever backend serves it
+    #[serde(default)]
+    pub modules: GatewayModulesConfig,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    /// Declarative model-to-backend routing table consulted by `Router::route` before
+    /// its substring-based fallback heuristic
+    #[serde(default)]
+    pub routing: RoutingConfig,
 }
 
 /// Server configuration
@@ -41,12 +51,34 @@ pub struct AuthConfig {
     pub enabled: bool,
     #[serde(default)]
     pub api_keys: Vec<String>,
+    /// Base64-encoded ed25519 signing key seed used to mint and verify signed image URL
+    /// access tokens. When unset, image URLs carry no token and are gated by `api_keys` only.
+    #[serde(default)]
+    pub token_signing_key: Option<String>,
+    /// Lifetime in seconds of a minted signed image URL token
+    #[serde(default = "default_token_ttl_secs")]
+    pub token_ttl_secs: u64,
+    /// Per-API-key rate limit overrides, keyed by the API key value. A key not present
+    /// here falls back to the global `RateLimitConfig` quota.
+    #[serde(default)]
+    pub rate_limit_overrides: std::collections::HashMap<String, KeyRateLimit>,
+}
+
+/// Per-key override of the global request-rate quota
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KeyRateLimit {
+    pub requests_per_second: u32,
+    pub burst_size: u32,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_token_ttl_secs() -> u64 {
+    3600
+}
+
 /// Rate limiting configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RateLimitConfig {
@@ -56,6 +88,13 @@ pub struct RateLimitConfig {
     pub requests_per_second: u32,
     #[serde(default = "default_burst")]
     pub burst_size: u32,
+    /// Default quota for unauthenticated, IP-keyed clients. Kept stricter than the
+    /// authenticated default since an anonymous bucket is often shared by many more
+    /// distinct real clients (e.g. everyone behind the same NAT or proxy).
+    #[serde(default = "default_anonymous_rps")]
+    pub anonymous_requests_per_second: u32,
+    #[serde(default = "default_anonymous_burst")]
+    pub anonymous_burst_size: u32,
 }
 
 fn default_rps() -> u32 {
@@ -66,6 +105,30 @@ fn default_burst() -> u32 {
     200
 }
 
+fn default_anonymous_rps() -> u32 {
+    20
+}
+
+fn default_anonymous_burst() -> u32 {
+    40
+}
+
+/// Response compression configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompressionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Minimum response body size in bytes before gzip/brotli compression is applied, so
+    /// tiny bodies like a `RateLimitError` JSON payload aren't spent CPU compressing for
+    /// no real savings
+    #[serde(default = "default_compression_min_size_bytes")]
+    pub min_size_bytes: u16,
+}
+
+fn default_compression_min_size_bytes() -> u16 {
+    256
+}
+
 /// Storage configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StorageConfig {
@@ -73,12 +136,102 @@ pub struct StorageConfig {
     pub base_path: String,
     #[serde(default = "default_url_prefix")]
     pub url_prefix: String,
+    /// Maximum total size in bytes of the on-disk image cache (0 = unbounded)
+    #[serde(default = "default_max_cache_bytes")]
+    pub max_cache_bytes: u64,
+    /// Maximum number of entries tracked by the cache's LRU index (0 = unbounded)
+    #[serde(default = "default_max_cache_entries")]
+    pub max_cache_entries: u64,
+    /// Which `StorageBackend` the gateway persists generated images to: `"filesystem"`
+    /// (default) writes under `base_path`; `"s3"` writes to the bucket described by
+    /// `object_storage`, letting the gateway run statelessly behind many nodes
+    #[serde(default = "default_storage_backend")]
+    pub backend: String,
+    /// S3-compatible bucket settings, consulted when `backend` is `"s3"`
+    #[serde(default)]
+    pub object_storage: ObjectStorageConfig,
+    /// How often the background task runs `StorageBackend::cleanup` and
+    /// `cleanup_to_capacity` against persisted blobs
+    #[serde(default = "default_storage_cleanup_interval_secs")]
+    pub cleanup_interval_secs: u64,
+    /// Blobs last written (or, in object storage, last modified) longer ago than this are
+    /// deleted by the age-based cleanup pass
+    #[serde(default = "default_storage_cleanup_max_age_secs")]
+    pub cleanup_max_age_secs: u64,
+    /// Ceiling on total blob storage bytes; once exceeded, least-recently-used blobs
+    /// are evicted down to a low watermark (0 = unbounded)
+    #[serde(default)]
+    pub max_bytes: u64,
+    /// When set (e.g. `"webp"`), newly-saved blobs are eagerly transcoded to this
+    /// format and the derived variant cached alongside the original, for smaller
+    /// average footprint without waiting on an on-the-fly transcode at read time.
+    /// `None` (the default) disables eager transcoding.
+    #[serde(default)]
+    pub preferred_save_format: Option<String>,
 }
 
 fn default_storage_path() -> String {
     "./generated_images".to_string()
 }
 
+fn default_storage_cleanup_interval_secs() -> u64 {
+    3600 // 1 hour
+}
+
+fn default_storage_cleanup_max_age_secs() -> u64 {
+    7 * 24 * 3600 // 1 week
+}
+
+fn default_storage_backend() -> String {
+    "filesystem".to_string()
+}
+
+/// S3-compatible object storage settings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ObjectStorageConfig {
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default = "default_object_storage_region")]
+    pub region: String,
+    /// Key prefix under which all blobs are written, e.g. `"gen-serving-gateway"`
+    #[serde(default)]
+    pub prefix: String,
+    /// Override for non-AWS S3-compatible stores (MinIO, R2, ...); `None` talks to AWS
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Falls back to the standard AWS credential chain (environment, instance profile)
+    /// when either is `None`
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+}
+
+impl Default for ObjectStorageConfig {
+    fn default() -> Self {
+        Self {
+            bucket: String::new(),
+            region: default_object_storage_region(),
+            prefix: String::new(),
+            endpoint: None,
+            access_key_id: None,
+            secret_access_key: None,
+        }
+    }
+}
+
+fn default_object_storage_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_max_cache_bytes() -> u64 {
+    1024 * 1024 * 1024 // 1 GiB
+}
+
+fn default_max_cache_entries() -> u64 {
+    10_000
+}
+
 fn default_url_prefix() -> String {
     "http://localhost:8080/images".to_string()
 }
@@ -106,9 +259,18 @@ pub struct BackendConfig {
     pub name: String,
     #[serde(default = "default_protocol")]
     pub protocol: String,
+    /// Which HTTP request/response shape this backend speaks: "openai", "automatic1111",
+    /// or "generic" (the default), which probes a handful of common shapes blindly.
+    /// Ignored by the gRPC backend.
+    #[serde(default = "default_api_style")]
+    pub api_style: String,
     pub endpoints: Vec<String>,
     #[serde(default = "default_health_check_path")]
     pub health_check_path: String,
+    /// Service name passed to `grpc.health.v1.Health/Check` for gRPC backends.
+    /// Empty string (the default) requests the backend's overall serving status.
+    #[serde(default)]
+    pub health_check_service: String,
     #[serde(default = "default_health_check_interval")]
     pub health_check_interval_secs: u64,
     #[serde(default = "default_timeout")]
@@ -117,12 +279,345 @@ pub struct BackendConfig {
     pub weight: u32,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Strategy for selecting among this backend's own endpoints: "round_robin"
+    /// (default), "weighted", "least_conn", or "consistent_hash"
+    #[serde(default = "default_strategy")]
+    pub strategy: String,
+    /// Per-backend gateway module run order. Parsed from configuration but not applied:
+    /// every backend runs the global `GatewayModulesConfig.order` chain
+    #[serde(default)]
+    pub module_order: Option<Vec<String>>,
+    /// Consecutive failures before an endpoint's circuit breaker trips to `Open`
+    #[serde(default = "default_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32,
+    /// Cooldown in seconds before a tripped endpoint's breaker allows a `HalfOpen` trial
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    pub circuit_breaker_cooldown_secs: u64,
+    /// Connection pooling and keep-alive tuning for the HTTP backend's `reqwest::Client`
+    /// (ignored by the gRPC backend, which pools channels itself)
+    #[serde(default)]
+    pub transport: TransportConfig,
+    /// Retry policy applied around `HttpBackend::generate`'s request loop
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// TLS tuning for backends behind a private CA or requiring mutual TLS (ignored
+    /// by the gRPC backend, which configures its own channel TLS)
+    #[serde(default)]
+    pub tls: TlsConfig,
+}
+
+fn default_circuit_breaker_threshold() -> u32 {
+    3
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    30
+}
+
+/// Transport-level tuning for `HttpBackend`'s underlying HTTP client
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransportConfig {
+    /// Time allowed to establish a TCP (and TLS) connection, separate from
+    /// `BackendConfig.timeout_ms`'s overall request/read timeout
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// Maximum idle (warm, keep-alive) connections kept per endpoint host
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle pooled connection is kept before being closed
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// TCP keep-alive idle time before the OS starts sending probes on an otherwise
+    /// quiet connection
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+    /// Disable Nagle's algorithm so small request/response frames aren't delayed
+    #[serde(default = "default_true")]
+    pub tcp_nodelay: bool,
+    /// Request TCP Fast Open to shave a round trip off connection setup. Reserved for
+    /// when the HTTP client crate exposes a way to set `TCP_FASTOPEN`; currently a no-op.
+    #[serde(default)]
+    pub tcp_fast_open: bool,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: default_connect_timeout_ms(),
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            tcp_nodelay: true,
+            tcp_fast_open: false,
+        }
+    }
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    8
+}
+
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+/// Retry policy for `HttpBackend::generate`, applied with full-jitter exponential backoff
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first; `1` disables retries
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Base delay in milliseconds for attempt 0's backoff window
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+    /// Upper bound in milliseconds on the computed backoff window, regardless of attempt
+    #[serde(default = "default_retry_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            base_delay_ms: default_retry_base_delay_ms(),
+            max_delay_ms: default_retry_max_delay_ms(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    100
+}
+
+fn default_retry_max_delay_ms() -> u64 {
+    5_000
+}
+
+/// TLS tuning for `HttpBackend`'s underlying HTTP client
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct TlsConfig {
+    /// PEM file with one or more additional CA certificates to trust, for backends
+    /// deployed behind a private CA instead of a publicly trusted one
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// PEM file with the client certificate to present for mutual TLS
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    /// PEM file with `client_cert_path`'s private key; required alongside it
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+    /// Skip certificate validation entirely. Only for self-signed certs in local/dev
+    /// environments; never enable this against a production backend.
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    /// Override the `Host` header sent on every request to this backend, for endpoints
+    /// reached by IP or through a name that doesn't match the certificate or the TLS
+    /// terminator's routing rules. `reqwest` doesn't expose the raw TLS SNI field on its
+    /// stable client builder, so this is the practical equivalent most proxies route on.
+    #[serde(default)]
+    pub sni_override: Option<String>,
+}
+
+/// Configuration for the gateway's pluggable request/response filter pipeline
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct GatewayModulesConfig {
+    #[serde(default)]
+    pub blocklist: BlocklistConfig,
+    #[serde(default)]
+    pub param_clamp: ParamClampConfig,
+    #[serde(default)]
+    pub watermark: WatermarkConfig,
+    #[serde(default)]
+    pub prompt_safety: PromptSafetyConfig,
+    /// Run order of enabled modules, by name ("blocklist", "param_clamp", "watermark",
+    /// "prompt_safety")
+    #[serde(default = "default_module_order")]
+    pub order: Vec<String>,
+}
+
+fn default_module_order() -> Vec<String> {
+    vec![
+        "blocklist".to_string(),
+        "prompt_safety".to_string(),
+        "param_clamp".to_string(),
+        "watermark".to_string(),
+    ]
+}
+
+/// Declarative model-to-backend routing table, consulted by `Router::route` before its
+/// substring-based fallback heuristic
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct RoutingConfig {
+    /// Ordered list of rules; the first whose pattern matches the request's model wins
+    #[serde(default)]
+    pub model_routes: Vec<ModelRouteConfig>,
+}
+
+/// One routing rule: requests whose model matches `pattern` are sent to one of
+/// `backends`, weighted proportionally when more than one is listed
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelRouteConfig {
+    /// Model pattern to match against: interpreted according to `match_type`
+    pub pattern: String,
+    /// How `pattern` is interpreted: "exact" (default), "glob" (`*`/`?` wildcards), or
+    /// "regex"
+    #[serde(default = "default_match_type")]
+    pub match_type: String,
+    /// Backend names this rule may route to
+    pub backends: Vec<ModelRouteBackend>,
+}
+
+fn default_match_type() -> String {
+    "exact".to_string()
+}
+
+/// A single backend target within a `ModelRouteConfig`, with its relative share of the
+/// rule's traffic
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelRouteBackend {
+    pub name: String,
+    #[serde(default = "default_route_weight")]
+    pub weight: u32,
+}
+
+fn default_route_weight() -> u32 {
+    1
+}
+
+/// Prompt moderation filter configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlocklistConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub blocked_terms: Vec<String>,
+    /// "reject" (default) or "redact"
+    #[serde(default = "default_blocklist_action")]
+    pub action: String,
+}
+
+impl Default for BlocklistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            blocked_terms: vec![],
+            action: default_blocklist_action(),
+        }
+    }
+}
+
+fn default_blocklist_action() -> String {
+    "reject".to_string()
+}
+
+/// Parameter-clamping filter configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParamClampConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_max_width")]
+    pub max_width: u32,
+    #[serde(default = "default_max_height")]
+    pub max_height: u32,
+    #[serde(default = "default_max_n")]
+    pub max_n: u32,
+    #[serde(default = "default_max_inference_steps")]
+    pub max_inference_steps: u32,
+}
+
+impl Default for ParamClampConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_width: default_max_width(),
+            max_height: default_max_height(),
+            max_n: default_max_n(),
+            max_inference_steps: default_max_inference_steps(),
+        }
+    }
+}
+
+fn default_max_width() -> u32 {
+    2048
+}
+
+fn default_max_height() -> u32 {
+    2048
+}
+
+fn default_max_n() -> u32 {
+    10
+}
+
+fn default_max_inference_steps() -> u32 {
+    150
+}
+
+/// Response watermark filter configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WatermarkConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_watermark_suffix")]
+    pub suffix: String,
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            suffix: default_watermark_suffix(),
+        }
+    }
+}
+
+fn default_watermark_suffix() -> String {
+    "(ai-generated)".to_string()
+}
+
+/// Prompt normalization and default negative-prompt injection filter configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PromptSafetyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Terms appended to every request's negative prompt (e.g. standing safety exclusions)
+    #[serde(default)]
+    pub default_negative_terms: Vec<String>,
+}
+
+impl Default for PromptSafetyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_negative_terms: vec![],
+        }
+    }
+}
+
+fn default_strategy() -> String {
+    "round_robin".to_string()
 }
 
 fn default_protocol() -> String {
     "http".to_string()
 }
 
+fn default_api_style() -> String {
+    "generic".to_string()
+}
+
 fn default_health_check_path() -> String {
     "/health".to_string()
 }
@@ -156,7 +651,9 @@ impl Settings {
             .set_default("rate_limit.requests_per_second", 100)?
             .set_default("rate_limit.burst_size", 200)?
             // Load from configuration file
-            .add_source(File::with_name(path.as_ref().to_str().unwrap_or("config/default")).required(false))
+            .add_source(
+                File::with_name(path.as_ref().to_str().unwrap_or("config/default")).required(false),
+            )
             // Override with environment variables (prefixed with IMG_SERVING_)
             .add_source(
                 Environment::with_prefix("IMG_SERVING")
@@ -186,17 +683,72 @@ impl Settings {
                 )));
             }
             if backend.endpoints.is_empty() {
-                return Err(AppError::Config(config::ConfigError::Message(
-                    format!("Backend '{}' must have at least one endpoint", backend.name),
-                )));
+                return Err(AppError::Config(config::ConfigError::Message(format!(
+                    "Backend '{}' must have at least one endpoint",
+                    backend.name
+                ))));
             }
             if !["http", "grpc"].contains(&backend.protocol.as_str()) {
-                return Err(AppError::Config(config::ConfigError::Message(
-                    format!(
-                        "Backend '{}' has invalid protocol '{}'. Must be 'http' or 'grpc'",
-                        backend.name, backend.protocol
-                    ),
-                )));
+                return Err(AppError::Config(config::ConfigError::Message(format!(
+                    "Backend '{}' has invalid protocol '{}'. Must be 'http' or 'grpc'",
+                    backend.name, backend.protocol
+                ))));
+            }
+        }
+
+        // Validate storage backend selection
+        if !["filesystem", "s3", "object_storage"].contains(&self.storage.backend.as_str()) {
+            return Err(AppError::Config(config::ConfigError::Message(format!(
+                "Storage backend '{}' is invalid. Must be 'filesystem' or 's3'",
+                self.storage.backend
+            ))));
+        }
+        if self.storage.backend != "filesystem" && self.storage.object_storage.bucket.is_empty() {
+            return Err(AppError::Config(config::ConfigError::Message(
+                "storage.object_storage.bucket must be set when storage.backend is 's3'"
+                    .to_string(),
+            )));
+        }
+
+        // Validate model routing table. Beyond checking `match_type` is a known
+        // variant, actually attempt to compile `pattern` as that match type (mirroring
+        // `gateway::router::ModelPattern::compile`) so a typo'd regex/glob is caught at
+        // startup instead of silently dropping the whole routing table later when
+        // `Router::set_model_routes` compiles it for real.
+        for route in &self.routing.model_routes {
+            if route.backends.is_empty() {
+                return Err(AppError::Config(config::ConfigError::Message(format!(
+                    "Model route '{}' must list at least one backend",
+                    route.pattern
+                ))));
+            }
+            match route.match_type.as_str() {
+                "exact" => {}
+                "glob" => {
+                    let segments: Vec<String> =
+                        route.pattern.split('*').map(regex::escape).collect();
+                    let regex_str = format!("^{}$", segments.join(".*"));
+                    if let Err(e) = Regex::new(&regex_str) {
+                        return Err(AppError::Config(config::ConfigError::Message(format!(
+                            "Model route '{}' has an invalid glob pattern: {}",
+                            route.pattern, e
+                        ))));
+                    }
+                }
+                "regex" => {
+                    if let Err(e) = Regex::new(&route.pattern) {
+                        return Err(AppError::Config(config::ConfigError::Message(format!(
+                            "Model route '{}' has an invalid regex pattern: {}",
+                            route.pattern, e
+                        ))));
+                    }
+                }
+                other => {
+                    return Err(AppError::Config(config::ConfigError::Message(format!(
+                        "Model route '{}' has invalid match_type '{}'. Must be 'exact', 'glob', or 'regex'",
+                        route.pattern, other
+                    ))));
+                }
             }
         }
 
@@ -214,21 +766,40 @@ impl Default for Settings {
             auth: AuthConfig {
                 enabled: true,
                 api_keys: vec![],
+                token_signing_key: None,
+                token_ttl_secs: default_token_ttl_secs(),
+                rate_limit_overrides: std::collections::HashMap::new(),
             },
             rate_limit: RateLimitConfig {
                 enabled: true,
                 requests_per_second: default_rps(),
                 burst_size: default_burst(),
+                anonymous_requests_per_second: default_anonymous_rps(),
+                anonymous_burst_size: default_anonymous_burst(),
             },
             storage: StorageConfig {
                 base_path: default_storage_path(),
                 url_prefix: default_url_prefix(),
+                max_cache_bytes: default_max_cache_bytes(),
+                max_cache_entries: default_max_cache_entries(),
+                backend: default_storage_backend(),
+                object_storage: ObjectStorageConfig::default(),
+                cleanup_interval_secs: default_storage_cleanup_interval_secs(),
+                cleanup_max_age_secs: default_storage_cleanup_max_age_secs(),
+                max_bytes: 0,
+                preferred_save_format: None,
             },
             logging: LoggingConfig {
                 level: default_log_level(),
                 format: default_log_format(),
             },
             backends: vec![],
+            modules: GatewayModulesConfig::default(),
+            routing: RoutingConfig::default(),
+            compression: CompressionConfig {
+                enabled: true,
+                min_size_bytes: default_compression_min_size_bytes(),
+            },
         }
     }
 }
@@ -246,4 +817,3 @@ mod tests {
         assert!(settings.rate_limit.enabled);
     }
 }
-
@@ -35,6 +35,9 @@ pub enum AppError {
     #[error("Authentication failed: {0}")]
     AuthenticationFailed(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
@@ -49,6 +52,9 @@ pub enum AppError {
 
     #[error("Internal error: {0}")]
     Internal(String),
+
+    #[error("Queue full: {0}")]
+    QueueFull(String),
 }
 
 /// Error response format (OpenAI compatible)
@@ -69,17 +75,47 @@ impl IntoResponse for AppError {
         let (status, error_type, code) = match &self {
             AppError::Config(_) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
             AppError::Io(_) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
-            AppError::Json(_) => (StatusCode::BAD_REQUEST, "invalid_request_error", Some("invalid_json")),
+            AppError::Json(_) => (
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                Some("invalid_json"),
+            ),
             AppError::HttpClient(_) => (StatusCode::BAD_GATEWAY, "backend_error", None),
             AppError::Grpc(_) => (StatusCode::BAD_GATEWAY, "backend_error", None),
-            AppError::BackendNotFound(_) => (StatusCode::NOT_FOUND, "not_found_error", Some("backend_not_found")),
-            AppError::NoHealthyBackends(_) => (StatusCode::SERVICE_UNAVAILABLE, "server_error", Some("no_healthy_backends")),
-            AppError::AuthenticationFailed(_) => (StatusCode::UNAUTHORIZED, "authentication_error", Some("invalid_api_key")),
-            AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "rate_limit_error", Some("rate_limit_exceeded")),
+            AppError::BackendNotFound(_) => (
+                StatusCode::NOT_FOUND,
+                "not_found_error",
+                Some("backend_not_found"),
+            ),
+            AppError::NoHealthyBackends(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "server_error",
+                Some("no_healthy_backends"),
+            ),
+            AppError::AuthenticationFailed(_) => (
+                StatusCode::UNAUTHORIZED,
+                "authentication_error",
+                Some("invalid_api_key"),
+            ),
+            AppError::Forbidden(_) => (
+                StatusCode::FORBIDDEN,
+                "authentication_error",
+                Some("invalid_access_token"),
+            ),
+            AppError::RateLimitExceeded => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limit_error",
+                Some("rate_limit_exceeded"),
+            ),
             AppError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, "invalid_request_error", None),
             AppError::BackendError(_) => (StatusCode::BAD_GATEWAY, "backend_error", None),
             AppError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, "timeout_error", None),
             AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", None),
+            AppError::QueueFull(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "server_error",
+                Some("queue_full"),
+            ),
         };
 
         let body = Json(ErrorResponse {
@@ -96,4 +132,3 @@ impl IntoResponse for AppError {
 
 /// Result type alias for convenience
 pub type Result<T> = std::result::Result<T, AppError>;
-
@@ -1,30 +1,60 @@
 //! Health check manager for monitoring backend health
 
 use dashmap::DashMap;
+use rand::Rng;
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 use crate::backend::registry::BackendRegistry;
+use crate::backend::traits::CircuitState;
+use crate::error::AppError;
 
-/// Health status of a backend
+/// Minimum number of sliding-window samples `record_success`/`record_failure` must have
+/// seen before outlier ejection is evaluated, so a breaker doesn't trip on one or two
+/// unlucky requests right after startup
+const MIN_OUTLIER_SAMPLES: usize = 5;
+
+/// Health status of a backend, including its circuit breaker state
 #[derive(Debug, Clone)]
 pub struct HealthStatus {
+    /// Convenience flag for callers that just want a yes/no answer without reasoning
+    /// about the breaker: false from the moment the breaker trips until it closes again,
+    /// including while a `HalfOpen` trial is running
     pub healthy: bool,
-    pub last_check: std::time::Instant,
+    pub last_check: Instant,
     pub consecutive_failures: u32,
     pub consecutive_successes: u32,
+    /// Closed (normal probing), Open (excluded from routing, backing off), or HalfOpen
+    /// (the current probe is this backend's one trial request)
+    pub circuit_state: CircuitState,
+    /// When the breaker last tripped to `Open`
+    pub open_since: Option<Instant>,
+    /// Current backoff delay before the next probe is allowed while `Open`; doubles on
+    /// each failed trial up to `max_backoff`
+    pub backoff: Duration,
+    /// Number of consecutive trips, used to compute the exponential backoff
+    consecutive_opens: u32,
+    /// Most recent `record_success`/`record_failure` outcomes, oldest first, capped at
+    /// `HealthCheckManager::outlier_window`; used for passive outlier ejection
+    outcomes: VecDeque<bool>,
 }
 
 impl Default for HealthStatus {
     fn default() -> Self {
         Self {
             healthy: true, // Assume healthy until proven otherwise
-            last_check: std::time::Instant::now(),
+            last_check: Instant::now(),
             consecutive_failures: 0,
             consecutive_successes: 0,
+            circuit_state: CircuitState::Closed,
+            open_since: None,
+            backoff: Duration::ZERO,
+            consecutive_opens: 0,
+            outcomes: VecDeque::new(),
         }
     }
 }
@@ -34,21 +64,80 @@ pub struct HealthCheckManager {
     registry: Arc<BackendRegistry>,
     health_status: DashMap<String, HealthStatus>,
     check_task: RwLock<Option<JoinHandle<()>>>,
-    /// Number of consecutive failures before marking unhealthy
+    /// Number of consecutive failures before the breaker trips to `Open`
     failure_threshold: u32,
-    /// Number of consecutive successes before marking healthy again
-    recovery_threshold: u32,
+    /// Base backoff delay before the first `HalfOpen` trial after a trip
+    base_backoff: Duration,
+    /// Upper bound the exponential backoff is capped at
+    max_backoff: Duration,
+    /// Whether to randomize the backoff within `[0, computed_backoff]` instead of using
+    /// it exactly, to avoid every backend's probes re-synchronizing after a shared outage
+    jitter: bool,
+    /// Number of most recent `record_success`/`record_failure` outcomes outlier ejection
+    /// is evaluated over
+    outlier_window: usize,
+    /// Failure rate within `outlier_window` (0.0-1.0) that immediately trips the breaker,
+    /// even while active probes are still passing
+    outlier_threshold: f32,
 }
 
 impl HealthCheckManager {
-    /// Create a new health check manager
+    /// Create a new health check manager with default circuit breaker tuning
     pub fn new(registry: Arc<BackendRegistry>) -> Self {
+        Self::with_circuit_breaker(
+            registry,
+            3,
+            Duration::from_secs(5),
+            Duration::from_secs(300),
+            true,
+        )
+    }
+
+    /// Create a new health check manager with custom circuit breaker tuning and default
+    /// outlier-ejection thresholds
+    pub fn with_circuit_breaker(
+        registry: Arc<BackendRegistry>,
+        failure_threshold: u32,
+        base_backoff: Duration,
+        max_backoff: Duration,
+        jitter: bool,
+    ) -> Self {
+        Self::with_outlier_detection(
+            registry,
+            failure_threshold,
+            base_backoff,
+            max_backoff,
+            jitter,
+            20,
+            0.5,
+        )
+    }
+
+    /// Create a new health check manager with custom circuit breaker and passive
+    /// outlier-ejection tuning. Outlier ejection fuses with the active prober: a backend
+    /// that fails more than `outlier_threshold` of its last `outlier_window` live requests
+    /// (reported via `record_success`/`record_failure`) is tripped immediately, instead
+    /// of waiting for periodic probes to notice.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_outlier_detection(
+        registry: Arc<BackendRegistry>,
+        failure_threshold: u32,
+        base_backoff: Duration,
+        max_backoff: Duration,
+        jitter: bool,
+        outlier_window: usize,
+        outlier_threshold: f32,
+    ) -> Self {
         Self {
             registry,
             health_status: DashMap::new(),
             check_task: RwLock::new(None),
-            failure_threshold: 3,
-            recovery_threshold: 2,
+            failure_threshold: failure_threshold.max(1),
+            base_backoff,
+            max_backoff,
+            jitter,
+            outlier_window: outlier_window.max(MIN_OUTLIER_SAMPLES),
+            outlier_threshold,
         }
     }
 
@@ -57,41 +146,61 @@ impl HealthCheckManager {
         let registry = self.registry.clone();
         let health_status = self.health_status.clone();
         let failure_threshold = self.failure_threshold;
-        let recovery_threshold = self.recovery_threshold;
+        let base_backoff = self.base_backoff;
+        let max_backoff = self.max_backoff;
+        let jitter = self.jitter;
 
         let handle = tokio::spawn(async move {
             let interval = Duration::from_secs(interval_secs);
-            
+
             loop {
-                // Check all backends
+                // Check all backends, skipping any still backing off in `Open`
                 for backend in registry.get_all() {
                     let name = backend.name().to_string();
+
+                    if !Self::probe_due(&health_status, &name) {
+                        continue;
+                    }
+                    Self::enter_half_open(&health_status, &name);
+
                     let is_healthy = backend.health_check().await;
 
                     let mut status = health_status
                         .entry(name.clone())
                         .or_insert_with(HealthStatus::default);
-
-                    status.last_check = std::time::Instant::now();
+                    status.last_check = Instant::now();
 
                     if is_healthy {
                         status.consecutive_failures = 0;
                         status.consecutive_successes += 1;
 
-                        if !status.healthy && status.consecutive_successes >= recovery_threshold {
-                            status.healthy = true;
-                            info!(backend = %name, "Backend recovered and marked healthy");
+                        if status.circuit_state != CircuitState::Closed {
+                            info!(backend = %name, "Backend probe succeeded; circuit closed");
                         }
+                        status.circuit_state = CircuitState::Closed;
+                        status.healthy = true;
+                        status.open_since = None;
+                        status.backoff = Duration::ZERO;
+                        status.consecutive_opens = 0;
                     } else {
                         status.consecutive_successes = 0;
                         status.consecutive_failures += 1;
 
-                        if status.healthy && status.consecutive_failures >= failure_threshold {
-                            status.healthy = false;
+                        let should_trip = match status.circuit_state {
+                            CircuitState::Closed => {
+                                status.consecutive_failures >= failure_threshold
+                            }
+                            CircuitState::HalfOpen => true,
+                            CircuitState::Open => false,
+                        };
+
+                        if should_trip {
+                            Self::trip(&mut status, base_backoff, max_backoff, jitter);
                             warn!(
                                 backend = %name,
                                 failures = status.consecutive_failures,
-                                "Backend marked unhealthy after consecutive failures"
+                                backoff_ms = status.backoff.as_millis() as u64,
+                                "Circuit opened; backing off before next probe"
                             );
                         }
                     }
@@ -99,6 +208,7 @@ impl HealthCheckManager {
                     debug!(
                         backend = %name,
                         healthy = status.healthy,
+                        circuit_state = ?status.circuit_state,
                         consecutive_failures = status.consecutive_failures,
                         consecutive_successes = status.consecutive_successes,
                         "Health check completed"
@@ -110,7 +220,67 @@ impl HealthCheckManager {
         });
 
         *self.check_task.write().await = Some(handle);
-        info!(interval_secs = interval_secs, "Started health check background task");
+        info!(
+            interval_secs = interval_secs,
+            "Started health check background task"
+        );
+    }
+
+    /// Whether a backend is due for a probe this tick: always true when `Closed`, and
+    /// true when `Open` only once its backoff has elapsed (at which point the probe that
+    /// follows is the `HalfOpen` trial)
+    fn probe_due(health_status: &DashMap<String, HealthStatus>, name: &str) -> bool {
+        let status = health_status
+            .entry(name.to_string())
+            .or_insert_with(HealthStatus::default);
+        match status.circuit_state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => false,
+            CircuitState::Open => status
+                .open_since
+                .map(|opened| opened.elapsed() >= status.backoff)
+                .unwrap_or(true),
+        }
+    }
+
+    /// Transition an `Open` breaker whose backoff just elapsed into `HalfOpen`, granting
+    /// the probe about to run its one trial
+    fn enter_half_open(health_status: &DashMap<String, HealthStatus>, name: &str) {
+        let mut status = health_status
+            .entry(name.to_string())
+            .or_insert_with(HealthStatus::default);
+        if status.circuit_state == CircuitState::Open {
+            status.circuit_state = CircuitState::HalfOpen;
+        }
+    }
+
+    /// Trip the breaker to `Open`, computing the next backoff as `base_backoff *
+    /// 2^consecutive_opens` (capped at `max_backoff`), optionally jittered
+    fn trip(
+        status: &mut HealthStatus,
+        base_backoff: Duration,
+        max_backoff: Duration,
+        jitter: bool,
+    ) {
+        // The shift cap is derived from how many doublings of `base_backoff` it takes to
+        // reach `max_backoff` (plus one, so the computed value is guaranteed to overshoot
+        // rather than plateau just under it), rather than a fixed constant — otherwise a
+        // `max_backoff` configured well above `base_backoff * 2^16` would never be reached.
+        let ratio = (max_backoff.as_nanos() / base_backoff.as_nanos().max(1)).max(1);
+        let max_shift = (ratio.ilog2() + 1).min(31);
+        let multiplier = 1u32 << status.consecutive_opens.min(max_shift);
+        let computed = base_backoff.saturating_mul(multiplier).min(max_backoff);
+
+        status.backoff = if jitter {
+            let jittered_ms = rand::thread_rng().gen_range(0..=computed.as_millis().max(1) as u64);
+            Duration::from_millis(jittered_ms)
+        } else {
+            computed
+        };
+        status.circuit_state = CircuitState::Open;
+        status.open_since = Some(Instant::now());
+        status.healthy = false;
+        status.consecutive_opens += 1;
     }
 
     /// Stop the health check background task
@@ -138,7 +308,7 @@ impl HealthCheckManager {
     pub async fn get_health_summary(&self) -> (usize, usize, usize) {
         let backends = self.registry.get_all();
         let total = backends.len();
-        
+
         let mut healthy = 0;
         let mut unhealthy = 0;
 
@@ -153,29 +323,145 @@ impl HealthCheckManager {
         (total, healthy, unhealthy)
     }
 
-    /// Force a health check for a specific backend
+    /// Force a health check for a specific backend, running it through the same circuit
+    /// breaker transitions as the background loop regardless of whether it was actually
+    /// due for a probe
     pub async fn check_now(&self, name: &str) -> Option<bool> {
         let backend = self.registry.get(name)?;
         let is_healthy = backend.health_check().await;
 
-        let mut status = self.health_status
+        let mut status = self
+            .health_status
             .entry(name.to_string())
             .or_insert_with(HealthStatus::default);
 
-        status.last_check = std::time::Instant::now();
-        status.healthy = is_healthy;
+        status.last_check = Instant::now();
 
         if is_healthy {
             status.consecutive_failures = 0;
             status.consecutive_successes += 1;
+            status.circuit_state = CircuitState::Closed;
+            status.healthy = true;
+            status.open_since = None;
+            status.backoff = Duration::ZERO;
+            status.consecutive_opens = 0;
         } else {
             status.consecutive_successes = 0;
             status.consecutive_failures += 1;
+
+            let should_trip = match status.circuit_state {
+                CircuitState::Closed => status.consecutive_failures >= self.failure_threshold,
+                CircuitState::HalfOpen => true,
+                CircuitState::Open => false,
+            };
+
+            if should_trip {
+                Self::trip(
+                    &mut status,
+                    self.base_backoff,
+                    self.max_backoff,
+                    self.jitter,
+                );
+            }
         }
 
         Some(is_healthy)
     }
 
+    /// Per-backend health detail, including circuit breaker state and current backoff,
+    /// for reporting which backends are in backoff (and for how long) via `/health`
+    pub fn get_all_statuses(&self) -> Vec<(String, HealthStatus)> {
+        self.health_status
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Record a successful live request against `name`, feeding the same
+    /// consecutive-success counter the active prober uses, plus the sliding window used
+    /// for outlier ejection. Closes the
+    /// circuit immediately, since a successful request is strictly stronger evidence of
+    /// health than a synthetic probe.
+    pub fn record_success(&self, name: &str) {
+        let mut status = self
+            .health_status
+            .entry(name.to_string())
+            .or_insert_with(HealthStatus::default);
+
+        status.consecutive_failures = 0;
+        status.consecutive_successes += 1;
+        Self::push_outcome(&mut status, true, self.outlier_window);
+
+        if status.circuit_state != CircuitState::Closed {
+            info!(backend = %name, "Backend recovered via live request; circuit closed");
+        }
+        status.circuit_state = CircuitState::Closed;
+        status.healthy = true;
+        status.open_since = None;
+        status.backoff = Duration::ZERO;
+        status.consecutive_opens = 0;
+    }
+
+    /// Record a failed live request against `name`, feeding the same consecutive-failure
+    /// counter the active prober uses. Independently checks the sliding-window failure
+    /// rate: if it exceeds `outlier_threshold`, the backend is ejected immediately even
+    /// though periodic probes may still be passing. Either trip reason reuses the same
+    /// `trip`/backoff/half-open machinery, so recovery works identically regardless of
+    /// which signal caused it.
+    pub fn record_failure(&self, name: &str, error: &AppError) {
+        let mut status = self
+            .health_status
+            .entry(name.to_string())
+            .or_insert_with(HealthStatus::default);
+
+        status.consecutive_successes = 0;
+        status.consecutive_failures += 1;
+        Self::push_outcome(&mut status, false, self.outlier_window);
+
+        let consecutive_trip = match status.circuit_state {
+            CircuitState::Closed => status.consecutive_failures >= self.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        let outlier_trip = status.circuit_state != CircuitState::Open
+            && Self::is_outlier(&status, self.outlier_threshold);
+
+        if consecutive_trip || outlier_trip {
+            Self::trip(
+                &mut status,
+                self.base_backoff,
+                self.max_backoff,
+                self.jitter,
+            );
+            warn!(
+                backend = %name,
+                error = %error,
+                failures = status.consecutive_failures,
+                outlier_ejected = outlier_trip,
+                backoff_ms = status.backoff.as_millis() as u64,
+                "Circuit opened from live request outcome"
+            );
+        }
+    }
+
+    /// Push a passive outcome into the sliding window, trimming it back down to `window`
+    fn push_outcome(status: &mut HealthStatus, success: bool, window: usize) {
+        status.outcomes.push_back(success);
+        while status.outcomes.len() > window {
+            status.outcomes.pop_front();
+        }
+    }
+
+    /// Whether the sliding window has enough samples and a high enough failure rate to
+    /// eject the backend outright
+    fn is_outlier(status: &HealthStatus, threshold: f32) -> bool {
+        if status.outcomes.len() < MIN_OUTLIER_SAMPLES {
+            return false;
+        }
+        let failures = status.outcomes.iter().filter(|ok| !**ok).count();
+        (failures as f32 / status.outcomes.len() as f32) > threshold
+    }
+
     /// Get all unhealthy backends
     pub fn get_unhealthy_backends(&self) -> Vec<String> {
         self.health_status
@@ -186,3 +472,61 @@ impl HealthCheckManager {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trip_doubles_backoff_up_to_max() {
+        let base = Duration::from_secs(5);
+        let max = Duration::from_secs(300);
+        let mut status = HealthStatus::default();
+
+        for expected_secs in [5u64, 10, 20, 40, 80] {
+            HealthCheckManager::trip(&mut status, base, max, false);
+            assert_eq!(status.backoff, Duration::from_secs(expected_secs));
+            assert_eq!(status.circuit_state, CircuitState::Open);
+        }
+
+        // Keeps doubling past the multiplier cap derived from the base/max ratio, but
+        // the result is always clamped to `max_backoff`
+        for _ in 0..5 {
+            HealthCheckManager::trip(&mut status, base, max, false);
+        }
+        assert_eq!(status.backoff, max);
+    }
+
+    #[test]
+    fn test_outlier_detection_requires_minimum_samples_and_threshold() {
+        let mut status = HealthStatus::default();
+
+        // Two failures out of two is a 100% failure rate, but below
+        // `MIN_OUTLIER_SAMPLES` so it must not eject yet
+        HealthCheckManager::push_outcome(&mut status, false, 20);
+        HealthCheckManager::push_outcome(&mut status, false, 20);
+        assert!(!HealthCheckManager::is_outlier(&status, 0.5));
+
+        // Enough samples now, but only 2 of 5 failed (40%), under the 50% threshold
+        HealthCheckManager::push_outcome(&mut status, true, 20);
+        HealthCheckManager::push_outcome(&mut status, true, 20);
+        HealthCheckManager::push_outcome(&mut status, true, 20);
+        assert!(!HealthCheckManager::is_outlier(&status, 0.5));
+
+        // One more failure tips it to 3 of 6 (50%), still not strictly over threshold
+        HealthCheckManager::push_outcome(&mut status, false, 20);
+        assert!(!HealthCheckManager::is_outlier(&status, 0.5));
+
+        // And one more makes it 4 of 7 (>50%), which ejects
+        HealthCheckManager::push_outcome(&mut status, false, 20);
+        assert!(HealthCheckManager::is_outlier(&status, 0.5));
+    }
+
+    #[test]
+    fn test_outcome_window_is_capped() {
+        let mut status = HealthStatus::default();
+        for _ in 0..10 {
+            HealthCheckManager::push_outcome(&mut status, true, 3);
+        }
+        assert_eq!(status.outcomes.len(), 3);
+    }
+}
@@ -1,12 +1,14 @@
 //! Load balancer implementation with multiple strategies
 
+use dashmap::DashMap;
 use parking_lot::RwLock;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use rand::Rng;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tracing::debug;
 
 use crate::backend::registry::BackendRegistry;
-use crate::backend::traits::ImageBackend;
+use crate::backend::traits::{BackendStatus, ImageBackend};
 use crate::error::{AppError, Result};
 
 /// Load balancing strategy
@@ -18,8 +20,13 @@ pub enum LoadBalancingStrategy {
     WeightedRoundRobin,
     /// Random selection
     Random,
-    /// Least connections (placeholder - needs connection tracking)
+    /// Routes to whichever healthy backend currently has the fewest in-flight
+    /// requests per unit of `weight()`
     LeastConnections,
+    /// Samples two healthy backends at random and routes to the less loaded of the two,
+    /// by the same weighted in-flight score as `LeastConnections`. Avoids the herd effect
+    /// strict least-connections can cause under high concurrency while staying O(1).
+    PowerOfTwoChoices,
 }
 
 impl Default for LoadBalancingStrategy {
@@ -34,6 +41,22 @@ pub struct LoadBalancer {
     strategy: RwLock<LoadBalancingStrategy>,
     round_robin_index: AtomicUsize,
     weighted_state: RwLock<WeightedRoundRobinState>,
+    /// In-flight request count per backend name, for `LeastConnections`/`PowerOfTwoChoices`
+    in_flight: DashMap<String, AtomicU64>,
+}
+
+/// RAII guard that reports a dispatch to the load balancer's in-flight tracking on
+/// construction and its completion on drop, regardless of whether the request
+/// succeeds, fails, or times out
+pub struct ConnectionGuard {
+    load_balancer: Arc<LoadBalancer>,
+    backend_name: String,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.load_balancer.release_dispatch(&self.backend_name);
+    }
 }
 
 /// State for weighted round-robin algorithm
@@ -53,6 +76,7 @@ impl LoadBalancer {
                 current_index: 0,
                 current_weight: 0,
             }),
+            in_flight: DashMap::new(),
         }
     }
 
@@ -81,7 +105,7 @@ impl LoadBalancer {
 
         // Get all healthy backends
         let healthy_backends = self.get_healthy_backends().await;
-        
+
         if healthy_backends.is_empty() {
             return Err(AppError::NoHealthyBackends("all".to_string()));
         }
@@ -89,19 +113,15 @@ impl LoadBalancer {
         // Select based on strategy
         let strategy = *self.strategy.read();
         let selected = match strategy {
-            LoadBalancingStrategy::RoundRobin => {
-                self.select_round_robin(&healthy_backends)
-            }
+            LoadBalancingStrategy::RoundRobin => self.select_round_robin(&healthy_backends),
             LoadBalancingStrategy::WeightedRoundRobin => {
                 self.select_weighted_round_robin(&healthy_backends)
             }
-            LoadBalancingStrategy::Random => {
-                self.select_random(&healthy_backends)
-            }
+            LoadBalancingStrategy::Random => self.select_random(&healthy_backends),
             LoadBalancingStrategy::LeastConnections => {
-                // Fall back to round-robin for now
-                self.select_round_robin(&healthy_backends)
+                self.select_least_connections(&healthy_backends)
             }
+            LoadBalancingStrategy::PowerOfTwoChoices => self.select_power_of_two(&healthy_backends),
         };
 
         debug!(
@@ -130,10 +150,7 @@ impl LoadBalancer {
     }
 
     /// Round-robin selection
-    fn select_round_robin(
-        &self,
-        backends: &[Arc<dyn ImageBackend>],
-    ) -> Arc<dyn ImageBackend> {
+    fn select_round_robin(&self, backends: &[Arc<dyn ImageBackend>]) -> Arc<dyn ImageBackend> {
         let index = self.round_robin_index.fetch_add(1, Ordering::Relaxed);
         backends[index % backends.len()].clone()
     }
@@ -154,7 +171,7 @@ impl LoadBalancer {
 
         loop {
             state.current_index = (state.current_index + 1) % backends.len();
-            
+
             if state.current_index == 0 {
                 state.current_weight -= gcd;
                 if state.current_weight <= 0 {
@@ -171,13 +188,119 @@ impl LoadBalancer {
     /// Random selection
     fn select_random(&self, backends: &[Arc<dyn ImageBackend>]) -> Arc<dyn ImageBackend> {
         use std::time::{SystemTime, UNIX_EPOCH};
-        
+
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default();
         let index = (now.as_nanos() as usize) % backends.len();
         backends[index].clone()
     }
+
+    /// Select the backend with the lowest in-flight-requests-per-unit-of-weight score
+    fn select_least_connections(
+        &self,
+        backends: &[Arc<dyn ImageBackend>],
+    ) -> Arc<dyn ImageBackend> {
+        backends
+            .iter()
+            .min_by(|a, b| self.load_score(a).total_cmp(&self.load_score(b)))
+            .cloned()
+            .unwrap_or_else(|| backends[0].clone())
+    }
+
+    /// Sample two distinct backends at random and route to the less loaded of the two
+    fn select_power_of_two(&self, backends: &[Arc<dyn ImageBackend>]) -> Arc<dyn ImageBackend> {
+        if backends.len() == 1 {
+            return backends[0].clone();
+        }
+
+        let (i, j) = {
+            let mut rng = rand::thread_rng();
+            let i = rng.gen_range(0..backends.len());
+            let mut j = rng.gen_range(0..backends.len() - 1);
+            if j >= i {
+                j += 1;
+            }
+            (i, j)
+        };
+
+        if self.load_score(&backends[i]) <= self.load_score(&backends[j]) {
+            backends[i].clone()
+        } else {
+            backends[j].clone()
+        }
+    }
+
+    /// In-flight requests per unit of `weight()`; lower means more spare capacity. A
+    /// heavier backend tolerates proportionally more concurrent requests before this
+    /// score deprioritizes it relative to a lighter one.
+    fn load_score(&self, backend: &Arc<dyn ImageBackend>) -> f64 {
+        let in_flight = self.in_flight_count(backend.name()) as f64;
+        let weight = backend.weight().max(1) as f64;
+        in_flight / weight
+    }
+
+    /// Current in-flight request count tracked for a backend by name
+    pub fn in_flight_count(&self, backend_name: &str) -> u64 {
+        self.in_flight
+            .get(backend_name)
+            .map(|counter| counter.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Record a request dispatched to `backend_name`, returning a guard that decrements
+    /// the in-flight count again when the request completes
+    pub fn track_dispatch(self: &Arc<Self>, backend_name: &str) -> ConnectionGuard {
+        self.in_flight
+            .entry(backend_name.to_string())
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(1, Ordering::Relaxed);
+
+        ConnectionGuard {
+            load_balancer: self.clone(),
+            backend_name: backend_name.to_string(),
+        }
+    }
+
+    fn release_dispatch(&self, backend_name: &str) {
+        if let Some(counter) = self.in_flight.get(backend_name) {
+            counter.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Weighted-random pick among `candidates`, each paired with the weight to select it
+    /// with — not necessarily the backend's own configured `weight()`. Used by `Router`
+    /// to spread a model route across several backends per an operator-configured
+    /// per-rule weight. Returns `None` if `candidates` is empty.
+    pub fn select_weighted(
+        &self,
+        candidates: &[(Arc<dyn ImageBackend>, u32)],
+    ) -> Option<Arc<dyn ImageBackend>> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let total: u32 = candidates.iter().map(|(_, weight)| (*weight).max(1)).sum();
+        let mut pick = rand::thread_rng().gen_range(0..total.max(1));
+
+        for (backend, weight) in candidates {
+            let weight = (*weight).max(1);
+            if pick < weight {
+                return Some(backend.clone());
+            }
+            pick -= weight;
+        }
+
+        candidates.last().map(|(backend, _)| backend.clone())
+    }
+
+    /// A backend's status, with `in_flight` filled in from this load balancer's tracked
+    /// connection count (the backend itself has no notion of cross-backend routing state)
+    pub fn backend_status(&self, backend: &Arc<dyn ImageBackend>) -> BackendStatus {
+        let mut status = backend.status();
+        status.in_flight = self.in_flight_count(backend.name());
+        status
+    }
 }
 
 /// Calculate greatest common divisor
@@ -200,4 +323,3 @@ mod tests {
         assert_eq!(gcd(7, 3), 1);
     }
 }
-
@@ -1,6 +1,9 @@
-//! Gateway module - Load balancing, health checking, and routing
+//! Gateway module - Load balancing, health checking, routing, and the pluggable
+//! request/response filter pipeline
 
 pub mod health_check;
 pub mod load_balancer;
+pub mod module;
+pub mod modules;
 pub mod router;
 
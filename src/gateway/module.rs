@@ -0,0 +1,169 @@
+//! Extensible filter pipeline for the gateway request/response path
+//!
+//! A `GatewayModule` mirrors the pluggable module hooks found in high-performance HTTP
+//! proxies: operators (or third parties) can insert auditing, transformation, or safety
+//! behavior around backend dispatch without touching the dispatch code itself. A
+//! `ModuleChain` runs an ordered list of modules over a request before it reaches a
+//! backend and over the response before it's handed back to the caller.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::backend::traits::{BackendStatus, GenerateRequest, GenerateResponse};
+use crate::config::GatewayModulesConfig;
+use crate::error::{AppError, Result};
+use crate::gateway::modules::{
+    BlocklistAction, BlocklistModule, ParamClampModule, PromptSafetyModule, WatermarkModule,
+};
+
+/// Outcome of a single filter hook
+pub enum FilterDecision {
+    /// Keep running the rest of the chain
+    Continue,
+    /// Stop the chain immediately and fail the request with this error
+    Halt(AppError),
+}
+
+/// A single stage in the gateway's request/response filter pipeline
+pub trait GatewayModule: Send + Sync {
+    /// Short name used to reference this module from configuration
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite the request before it's queued for dispatch
+    fn request_filter(&self, _request: &mut GenerateRequest) -> Result<FilterDecision> {
+        Ok(FilterDecision::Continue)
+    }
+
+    /// A second request-stage hook, run after every module's `request_filter` has passed,
+    /// for modules that need to see the request only once the rest of the chain has
+    /// already normalized it (e.g. enforcing limits after a rewrite)
+    fn request_body_filter(&self, _request: &mut GenerateRequest) -> Result<FilterDecision> {
+        Ok(FilterDecision::Continue)
+    }
+
+    /// Inspect or rewrite the response before it's returned to the caller
+    fn response_filter(&self, _response: &mut GenerateResponse) -> Result<FilterDecision> {
+        Ok(FilterDecision::Continue)
+    }
+
+    /// Observe the backend chosen for this request, after selection but before dispatch.
+    /// Modules can't rewrite the selection itself here (routing belongs to the
+    /// `LoadBalancer`), but can reject the request outright, e.g. to enforce a
+    /// per-tenant backend allowlist or collect selection metrics.
+    fn on_select(&self, _status: &BackendStatus) -> Result<FilterDecision> {
+        Ok(FilterDecision::Continue)
+    }
+}
+
+/// Runs an ordered list of `GatewayModule`s over a request and its eventual response
+#[derive(Clone, Default)]
+pub struct ModuleChain {
+    modules: Vec<Arc<dyn GatewayModule>>,
+}
+
+impl ModuleChain {
+    pub fn new(modules: Vec<Arc<dyn GatewayModule>>) -> Self {
+        Self { modules }
+    }
+
+    /// Run every module's `request_filter`, then every module's `request_body_filter`,
+    /// halting on the first module that rejects the request
+    pub fn run_request_filters(&self, request: &mut GenerateRequest) -> Result<()> {
+        for module in &self.modules {
+            match module.request_filter(request)? {
+                FilterDecision::Continue => {}
+                FilterDecision::Halt(e) => return Err(e),
+            }
+        }
+        for module in &self.modules {
+            match module.request_body_filter(request)? {
+                FilterDecision::Continue => {}
+                FilterDecision::Halt(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Run every module's `response_filter`, halting on the first rejection
+    pub fn run_response_filters(&self, response: &mut GenerateResponse) -> Result<()> {
+        for module in &self.modules {
+            match module.response_filter(response)? {
+                FilterDecision::Continue => {}
+                FilterDecision::Halt(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Run every module's `on_select` hook against the backend chosen for this request,
+    /// halting on the first rejection
+    pub fn run_select_filters(&self, status: &BackendStatus) -> Result<()> {
+        for module in &self.modules {
+            match module.on_select(status)? {
+                FilterDecision::Continue => {}
+                FilterDecision::Halt(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+}
+
+/// Build the module chain described by `config`, optionally overriding its run order.
+/// The gateway builds a single global chain with no override; `BackendConfig.module_order`
+/// is not passed here.
+pub fn build_module_chain(
+    config: &GatewayModulesConfig,
+    order_override: Option<&[String]>,
+) -> ModuleChain {
+    let mut available: HashMap<String, Arc<dyn GatewayModule>> = HashMap::new();
+
+    if config.blocklist.enabled {
+        let action = if config.blocklist.action == "redact" {
+            BlocklistAction::Redact
+        } else {
+            BlocklistAction::Reject
+        };
+        available.insert(
+            "blocklist".to_string(),
+            Arc::new(BlocklistModule::new(config.blocklist.blocked_terms.clone(), action)),
+        );
+    }
+
+    if config.param_clamp.enabled {
+        available.insert(
+            "param_clamp".to_string(),
+            Arc::new(ParamClampModule::new(
+                config.param_clamp.max_width,
+                config.param_clamp.max_height,
+                config.param_clamp.max_n,
+                config.param_clamp.max_inference_steps,
+            )),
+        );
+    }
+
+    if config.watermark.enabled {
+        available.insert(
+            "watermark".to_string(),
+            Arc::new(WatermarkModule::new(config.watermark.suffix.clone())),
+        );
+    }
+
+    if config.prompt_safety.enabled {
+        available.insert(
+            "prompt_safety".to_string(),
+            Arc::new(PromptSafetyModule::new(config.prompt_safety.default_negative_terms.clone())),
+        );
+    }
+
+    let order = order_override.unwrap_or(&config.order);
+    let modules = order
+        .iter()
+        .filter_map(|name| available.remove(name))
+        .collect();
+
+    ModuleChain::new(modules)
+}
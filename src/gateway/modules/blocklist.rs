@@ -0,0 +1,156 @@
+//! Prompt moderation filter: reject or redact prompts containing configured terms
+
+use crate::backend::traits::GenerateRequest;
+use crate::error::AppError;
+use crate::gateway::module::{FilterDecision, GatewayModule};
+
+/// What to do when a blocked term is found in a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlocklistAction {
+    /// Fail the request outright
+    Reject,
+    /// Mask every case-insensitive occurrence of the blocked terms with `*`s and let the
+    /// request continue
+    Redact,
+}
+
+pub struct BlocklistModule {
+    blocked_terms: Vec<String>,
+    action: BlocklistAction,
+}
+
+impl BlocklistModule {
+    pub fn new(blocked_terms: Vec<String>, action: BlocklistAction) -> Self {
+        Self { blocked_terms, action }
+    }
+
+    fn find_blocked_term(&self, prompt: &str) -> Option<&str> {
+        let lower = prompt.to_lowercase();
+        self.blocked_terms
+            .iter()
+            .find(|term| lower.contains(term.to_lowercase().as_str()))
+            .map(|term| term.as_str())
+    }
+}
+
+impl GatewayModule for BlocklistModule {
+    fn name(&self) -> &str {
+        "blocklist"
+    }
+
+    fn request_filter(&self, request: &mut GenerateRequest) -> crate::error::Result<FilterDecision> {
+        let Some(term) = self.find_blocked_term(&request.prompt) else {
+            return Ok(FilterDecision::Continue);
+        };
+
+        match self.action {
+            BlocklistAction::Reject => Ok(FilterDecision::Halt(AppError::InvalidRequest(format!(
+                "Prompt rejected: contains a blocked term ('{}')",
+                term
+            )))),
+            BlocklistAction::Redact => {
+                let mut term = term;
+                loop {
+                    let redacted = redact_term(&request.prompt, term);
+                    if redacted == request.prompt {
+                        break;
+                    }
+                    request.prompt = redacted;
+                    match self.find_blocked_term(&request.prompt) {
+                        Some(next) => term = next,
+                        None => break,
+                    }
+                }
+                Ok(FilterDecision::Continue)
+            }
+        }
+    }
+}
+
+/// Find `term` in `prompt`, case-insensitively, and replace the match with `*`s. The
+/// match position is located by scanning `prompt`'s own char boundaries rather than
+/// reusing a byte offset found in `prompt.to_lowercase()`: Unicode lowercasing isn't
+/// always length-preserving (e.g. `İ` U+0130 lowercases to a two-codepoint sequence),
+/// so an offset valid in the lowercased copy can land mid-codepoint in the original,
+/// panicking on the slice or silently corrupting surrounding text.
+fn redact_term(prompt: &str, term: &str) -> String {
+    let term_lower = term.to_lowercase();
+    let term_char_count = term.chars().count();
+    let char_starts: Vec<usize> = prompt.char_indices().map(|(i, _)| i).collect();
+
+    for (window_idx, &window_start) in char_starts.iter().enumerate() {
+        let end_idx = window_idx + term_char_count;
+        if end_idx > char_starts.len() {
+            break;
+        }
+        let window_end = char_starts.get(end_idx).copied().unwrap_or(prompt.len());
+        let window = &prompt[window_start..window_end];
+
+        if window.to_lowercase() == term_lower {
+            let mut redacted = String::with_capacity(prompt.len());
+            redacted.push_str(&prompt[..window_start]);
+            redacted.push_str(&"*".repeat(window.len()));
+            redacted.push_str(&prompt[window_end..]);
+            return redacted;
+        }
+    }
+
+    prompt.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reject_blocked_prompt() {
+        let module = BlocklistModule::new(vec!["forbidden".to_string()], BlocklistAction::Reject);
+        let mut request = sample_request("a forbidden thing");
+        let result = module.request_filter(&mut request).unwrap();
+        assert!(matches!(result, FilterDecision::Halt(_)));
+    }
+
+    #[test]
+    fn test_redact_blocked_prompt() {
+        let module = BlocklistModule::new(vec!["forbidden".to_string()], BlocklistAction::Redact);
+        let mut request = sample_request("a forbidden thing");
+        let result = module.request_filter(&mut request).unwrap();
+        assert!(matches!(result, FilterDecision::Continue));
+        assert_eq!(request.prompt, "a ********* thing");
+    }
+
+    #[test]
+    fn test_redact_handles_non_length_preserving_lowercasing() {
+        // `İ` (U+0130) lowercases to a two-codepoint sequence, so a naive byte offset
+        // taken from `prompt.to_lowercase()` would no longer line up with `prompt`'s
+        // own byte boundaries.
+        let module = BlocklistModule::new(vec!["forbidden".to_string()], BlocklistAction::Redact);
+        let mut request = sample_request("İ€forbidden");
+        let result = module.request_filter(&mut request).unwrap();
+        assert!(matches!(result, FilterDecision::Continue));
+        assert_eq!(request.prompt, "İ€*********");
+    }
+
+    #[test]
+    fn test_allows_clean_prompt() {
+        let module = BlocklistModule::new(vec!["forbidden".to_string()], BlocklistAction::Reject);
+        let mut request = sample_request("a nice landscape");
+        let result = module.request_filter(&mut request).unwrap();
+        assert!(matches!(result, FilterDecision::Continue));
+    }
+
+    fn sample_request(prompt: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            negative_prompt: None,
+            n: 1,
+            width: 512,
+            height: 512,
+            model: None,
+            seed: None,
+            guidance_scale: None,
+            num_inference_steps: None,
+            response_format: "url".to_string(),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Built-in `GatewayModule` implementations
+
+mod blocklist;
+mod param_clamp;
+mod prompt_safety;
+mod watermark;
+
+pub use blocklist::{BlocklistAction, BlocklistModule};
+pub use param_clamp::ParamClampModule;
+pub use prompt_safety::PromptSafetyModule;
+pub use watermark::WatermarkModule;
@@ -0,0 +1,91 @@
+//! Enforces configured upper bounds on generation parameters
+
+use crate::backend::traits::GenerateRequest;
+use crate::error::Result;
+use crate::gateway::module::{FilterDecision, GatewayModule};
+
+pub struct ParamClampModule {
+    max_width: u32,
+    max_height: u32,
+    max_n: u32,
+    max_inference_steps: u32,
+}
+
+impl ParamClampModule {
+    pub fn new(max_width: u32, max_height: u32, max_n: u32, max_inference_steps: u32) -> Self {
+        Self {
+            max_width,
+            max_height,
+            max_n,
+            max_inference_steps,
+        }
+    }
+}
+
+impl GatewayModule for ParamClampModule {
+    fn name(&self) -> &str {
+        "param_clamp"
+    }
+
+    fn request_filter(&self, request: &mut GenerateRequest) -> Result<FilterDecision> {
+        request.width = request.width.min(self.max_width);
+        request.height = request.height.min(self.max_height);
+        request.n = request.n.clamp(1, self.max_n);
+        if let Some(steps) = request.num_inference_steps {
+            request.num_inference_steps = Some(steps.min(self.max_inference_steps));
+        }
+        Ok(FilterDecision::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamps_oversized_dimensions() {
+        let module = ParamClampModule::new(1024, 1024, 4, 50);
+        let mut request = sample_request();
+        request.width = 4096;
+        request.height = 4096;
+        request.n = 100;
+        request.num_inference_steps = Some(500);
+
+        module.request_filter(&mut request).unwrap();
+
+        assert_eq!(request.width, 1024);
+        assert_eq!(request.height, 1024);
+        assert_eq!(request.n, 4);
+        assert_eq!(request.num_inference_steps, Some(50));
+    }
+
+    #[test]
+    fn test_leaves_compliant_request_untouched() {
+        let module = ParamClampModule::new(1024, 1024, 4, 50);
+        let mut request = sample_request();
+        request.width = 512;
+        request.height = 512;
+        request.n = 2;
+
+        module.request_filter(&mut request).unwrap();
+
+        assert_eq!(request.width, 512);
+        assert_eq!(request.height, 512);
+        assert_eq!(request.n, 2);
+    }
+
+    fn sample_request() -> GenerateRequest {
+        GenerateRequest {
+            prompt: "a landscape".to_string(),
+            negative_prompt: None,
+            n: 1,
+            width: 512,
+            height: 512,
+            model: None,
+            seed: None,
+            guidance_scale: None,
+            num_inference_steps: None,
+            response_format: "url".to_string(),
+        }
+    }
+}
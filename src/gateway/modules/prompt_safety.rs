@@ -0,0 +1,95 @@
+//! Normalizes prompt whitespace and ensures a minimum set of negative-prompt safety terms
+
+use crate::backend::traits::GenerateRequest;
+use crate::error::Result;
+use crate::gateway::module::{FilterDecision, GatewayModule};
+
+/// Collapses runs of whitespace in the prompt and appends a configured list of default
+/// negative-prompt terms (e.g. safety/style exclusions operators want applied to every
+/// request) to whatever the caller already supplied
+pub struct PromptSafetyModule {
+    default_negative_terms: Vec<String>,
+}
+
+impl PromptSafetyModule {
+    pub fn new(default_negative_terms: Vec<String>) -> Self {
+        Self { default_negative_terms }
+    }
+}
+
+impl GatewayModule for PromptSafetyModule {
+    fn name(&self) -> &str {
+        "prompt_safety"
+    }
+
+    fn request_filter(&self, request: &mut GenerateRequest) -> Result<FilterDecision> {
+        request.prompt = normalize_whitespace(&request.prompt);
+
+        if self.default_negative_terms.is_empty() {
+            return Ok(FilterDecision::Continue);
+        }
+
+        let defaults = self.default_negative_terms.join(", ");
+        request.negative_prompt = Some(match request.negative_prompt.take() {
+            Some(existing) if !existing.trim().is_empty() => format!("{}, {}", existing, defaults),
+            _ => defaults,
+        });
+
+        Ok(FilterDecision::Continue)
+    }
+}
+
+fn normalize_whitespace(prompt: &str) -> String {
+    prompt.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapses_whitespace() {
+        let module = PromptSafetyModule::new(vec![]);
+        let mut request = sample_request("a   cat\n\tsitting");
+
+        module.request_filter(&mut request).unwrap();
+
+        assert_eq!(request.prompt, "a cat sitting");
+    }
+
+    #[test]
+    fn test_injects_default_negative_terms_when_absent() {
+        let module = PromptSafetyModule::new(vec!["nsfw".to_string(), "gore".to_string()]);
+        let mut request = sample_request("a cat");
+
+        module.request_filter(&mut request).unwrap();
+
+        assert_eq!(request.negative_prompt.as_deref(), Some("nsfw, gore"));
+    }
+
+    #[test]
+    fn test_appends_default_terms_to_existing_negative_prompt() {
+        let module = PromptSafetyModule::new(vec!["nsfw".to_string()]);
+        let mut request = sample_request("a cat");
+        request.negative_prompt = Some("blurry".to_string());
+
+        module.request_filter(&mut request).unwrap();
+
+        assert_eq!(request.negative_prompt.as_deref(), Some("blurry, nsfw"));
+    }
+
+    fn sample_request(prompt: &str) -> GenerateRequest {
+        GenerateRequest {
+            prompt: prompt.to_string(),
+            negative_prompt: None,
+            n: 1,
+            width: 512,
+            height: 512,
+            model: None,
+            seed: None,
+            guidance_scale: None,
+            num_inference_steps: None,
+            response_format: "url".to_string(),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Response-side provenance watermark: tags generated images with a marker string
+
+use crate::backend::traits::GenerateResponse;
+use crate::error::Result;
+use crate::gateway::module::{FilterDecision, GatewayModule};
+
+pub struct WatermarkModule {
+    suffix: String,
+}
+
+impl WatermarkModule {
+    pub fn new(suffix: String) -> Self {
+        Self { suffix }
+    }
+}
+
+impl GatewayModule for WatermarkModule {
+    fn name(&self) -> &str {
+        "watermark"
+    }
+
+    fn response_filter(&self, response: &mut GenerateResponse) -> Result<FilterDecision> {
+        for image in &mut response.images {
+            image.revised_prompt = Some(match image.revised_prompt.take() {
+                Some(prompt) => format!("{} {}", prompt, self.suffix),
+                None => self.suffix.clone(),
+            });
+        }
+        Ok(FilterDecision::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::traits::GeneratedImage;
+
+    #[test]
+    fn test_appends_suffix_to_existing_revised_prompt() {
+        let module = WatermarkModule::new("(ai-generated)".to_string());
+        let mut response = GenerateResponse {
+            images: vec![GeneratedImage {
+                b64_json: None,
+                url: Some("http://example.com/a.png".to_string()),
+                revised_prompt: Some("a cat".to_string()),
+                seed: None,
+            }],
+            model: None,
+        };
+
+        module.response_filter(&mut response).unwrap();
+
+        assert_eq!(
+            response.images[0].revised_prompt.as_deref(),
+            Some("a cat (ai-generated)")
+        );
+    }
+
+    #[test]
+    fn test_sets_suffix_when_no_revised_prompt() {
+        let module = WatermarkModule::new("(ai-generated)".to_string());
+        let mut response = GenerateResponse {
+            images: vec![GeneratedImage {
+                b64_json: None,
+                url: None,
+                revised_prompt: None,
+                seed: None,
+            }],
+            model: None,
+        };
+
+        module.response_filter(&mut response).unwrap();
+
+        assert_eq!(response.images[0].revised_prompt.as_deref(), Some("(ai-generated)"));
+    }
+}
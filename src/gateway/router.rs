@@ -1,12 +1,16 @@
 //! Dynamic router for routing requests to appropriate backends
 
+use parking_lot::RwLock;
+use regex::Regex;
 use std::sync::Arc;
 use tracing::debug;
 
 use crate::backend::registry::BackendRegistry;
 use crate::backend::traits::ImageBackend;
+use crate::config::{ModelRouteConfig, Settings};
 use crate::error::{AppError, Result};
 use crate::gateway::health_check::HealthCheckManager;
+use crate::gateway::load_balancer::LoadBalancer;
 
 /// Router configuration
 #[derive(Debug, Clone)]
@@ -26,11 +30,94 @@ impl Default for RouterConfig {
     }
 }
 
+/// How a `RouteRule`'s `pattern` is matched against a request's model name
+#[derive(Debug, Clone)]
+enum ModelPattern {
+    Exact(String),
+    /// Compiled from a `*`/`?` glob by escaping literal segments and joining them with
+    /// `.*`/`.`, so glob matching reuses the same `Regex` engine as `Regex` patterns
+    Glob(Regex),
+    Regex(Regex),
+}
+
+impl ModelPattern {
+    fn compile(pattern: &str, match_type: &str) -> Result<Self> {
+        match match_type {
+            "exact" => Ok(Self::Exact(pattern.to_string())),
+            "glob" => Self::compile_glob(pattern).map(Self::Glob),
+            "regex" => Regex::new(pattern).map(Self::Regex).map_err(|e| {
+                AppError::Config(config::ConfigError::Message(format!(
+                    "invalid model route regex '{}': {}",
+                    pattern, e
+                )))
+            }),
+            other => Err(AppError::Config(config::ConfigError::Message(format!(
+                "unknown model route match_type '{}'",
+                other
+            )))),
+        }
+    }
+
+    fn compile_glob(pattern: &str) -> Result<Regex> {
+        let segments: Vec<String> = pattern
+            .split('*')
+            .map(|s| regex::escape(s).replace("\\?", "."))
+            .collect();
+        let regex_str = format!("^{}$", segments.join(".*"));
+        Regex::new(&regex_str).map_err(|e| {
+            AppError::Config(config::ConfigError::Message(format!(
+                "invalid model route glob '{}': {}",
+                pattern, e
+            )))
+        })
+    }
+
+    fn matches(&self, model: &str) -> bool {
+        match self {
+            Self::Exact(exact) => exact == model,
+            Self::Glob(re) | Self::Regex(re) => re.is_match(model),
+        }
+    }
+}
+
+/// One backend a matched rule may route to, with its relative weight for spreading
+/// traffic across several backends
+struct RouteTarget {
+    backend: String,
+    weight: u32,
+}
+
+/// A compiled routing rule: requests whose model matches `pattern` are routed to one of
+/// `targets`
+struct RouteRule {
+    pattern: ModelPattern,
+    targets: Vec<RouteTarget>,
+}
+
+impl RouteRule {
+    fn compile(config: &ModelRouteConfig) -> Result<Self> {
+        let pattern = ModelPattern::compile(&config.pattern, &config.match_type)?;
+        let targets = config
+            .backends
+            .iter()
+            .map(|b| RouteTarget {
+                backend: b.name.clone(),
+                weight: b.weight,
+            })
+            .collect();
+        Ok(Self { pattern, targets })
+    }
+}
+
 /// Dynamic router for backend selection
 pub struct Router {
     registry: Arc<BackendRegistry>,
     health_manager: Arc<HealthCheckManager>,
+    load_balancer: Arc<LoadBalancer>,
     config: RouterConfig,
+    /// Declarative model-to-backend routing table, consulted before `route_by_model`'s
+    /// substring heuristic. Behind a lock so `set_model_routes` can update it at runtime.
+    model_routes: RwLock<Vec<RouteRule>>,
 }
 
 impl Router {
@@ -38,27 +125,70 @@ impl Router {
     pub fn new(
         registry: Arc<BackendRegistry>,
         health_manager: Arc<HealthCheckManager>,
+        load_balancer: Arc<LoadBalancer>,
     ) -> Self {
-        Self {
+        Self::with_config(
             registry,
             health_manager,
-            config: RouterConfig::default(),
-        }
+            load_balancer,
+            RouterConfig::default(),
+        )
     }
 
     /// Create a new router with configuration
     pub fn with_config(
         registry: Arc<BackendRegistry>,
         health_manager: Arc<HealthCheckManager>,
+        load_balancer: Arc<LoadBalancer>,
         config: RouterConfig,
     ) -> Self {
         Self {
             registry,
             health_manager,
+            load_balancer,
             config,
+            model_routes: RwLock::new(Vec::new()),
         }
     }
 
+    /// Create a new router and load its model routing table from `settings.routing`.
+    /// `Settings::validate` already attempts to compile every rule's pattern before a
+    /// config is accepted, so this should never actually drop a rule in practice — but
+    /// `set_model_routes` re-validates anyway (skipping only the offending rule, not
+    /// the whole table) as defense in depth against a config that bypassed validation
+    /// (e.g. hot-reloaded or constructed directly rather than loaded from a file).
+    pub fn from_settings(
+        registry: Arc<BackendRegistry>,
+        health_manager: Arc<HealthCheckManager>,
+        load_balancer: Arc<LoadBalancer>,
+        settings: &Settings,
+    ) -> Self {
+        let router = Self::new(registry, health_manager, load_balancer);
+        router.set_model_routes(&settings.routing.model_routes);
+        router
+    }
+
+    /// Replace the model routing table. A rule whose pattern fails to compile is
+    /// skipped (logged as a warning) rather than dropping every other, valid rule in
+    /// the table.
+    pub fn set_model_routes(&self, routes: &[ModelRouteConfig]) {
+        let rules = routes
+            .iter()
+            .filter_map(|route| match RouteRule::compile(route) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    tracing::warn!(
+                        pattern = %route.pattern,
+                        error = %e,
+                        "Skipping model route with an invalid pattern"
+                    );
+                    None
+                }
+            })
+            .collect();
+        *self.model_routes.write() = rules;
+    }
+
     /// Route a request to an appropriate backend
     pub async fn route(
         &self,
@@ -70,8 +200,13 @@ impl Router {
             return self.get_healthy_backend(name).await;
         }
 
-        // Priority 2: Route based on model name
+        // Priority 2: Route based on model name, consulting the declarative routing
+        // table before falling back to the substring heuristic
         if let Some(model) = model {
+            if let Some(backend) = self.route_by_table(model).await {
+                return Ok(backend);
+            }
+
             if let Some(backend) = self.route_by_model(model).await {
                 return Ok(backend);
             }
@@ -89,7 +224,9 @@ impl Router {
             return self.get_any_healthy_backend().await;
         }
 
-        Err(AppError::NoHealthyBackends("No available backends".to_string()))
+        Err(AppError::NoHealthyBackends(
+            "No available backends".to_string(),
+        ))
     }
 
     /// Get a specific backend if it's healthy
@@ -114,25 +251,58 @@ impl Router {
         Ok(backend)
     }
 
+    /// Consult the declarative model routing table, in rule order, returning the first
+    /// rule that matches `model` and has at least one enabled, healthy target backend.
+    /// When a matching rule lists several backends, one is chosen by weighted random
+    /// selection via the `LoadBalancer`, proportioned by each target's configured weight.
+    async fn route_by_table(&self, model: &str) -> Option<Arc<dyn ImageBackend>> {
+        let rules = self.model_routes.read();
+
+        for rule in rules.iter() {
+            if !rule.pattern.matches(model) {
+                continue;
+            }
+
+            let candidates: Vec<(Arc<dyn ImageBackend>, u32)> = rule
+                .targets
+                .iter()
+                .filter_map(|target| {
+                    let backend = self.registry.get(&target.backend)?;
+                    if !backend.is_enabled() || !self.health_manager.is_healthy(backend.name()) {
+                        return None;
+                    }
+                    Some((backend, target.weight))
+                })
+                .collect();
+
+            if let Some(backend) = self.load_balancer.select_weighted(&candidates) {
+                debug!(backend = %backend.name(), model = %model, "Routed via model routing table");
+                return Some(backend);
+            }
+        }
+
+        None
+    }
+
     /// Route based on model name
     /// This can be extended to support model-to-backend mapping
     async fn route_by_model(&self, model: &str) -> Option<Arc<dyn ImageBackend>> {
         // Simple heuristic: look for backends that might support the model
         // This could be enhanced with a proper model registry
-        
+
         let model_lower = model.to_lowercase();
-        
+
         for backend in self.registry.get_all() {
             if !backend.is_enabled() {
                 continue;
             }
-            
+
             if !self.health_manager.is_healthy(backend.name()) {
                 continue;
             }
 
             let backend_name = backend.name().to_lowercase();
-            
+
             // Check if backend name matches model name pattern
             if model_lower.contains(&backend_name) || backend_name.contains(&model_lower) {
                 debug!(backend = %backend.name(), model = %model, "Routed by model name");
@@ -179,3 +349,40 @@ impl Router {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_pattern() {
+        let pattern = ModelPattern::compile("dall-e-3", "exact").unwrap();
+        assert!(pattern.matches("dall-e-3"));
+        assert!(!pattern.matches("dall-e-2"));
+    }
+
+    #[test]
+    fn test_glob_pattern() {
+        let pattern = ModelPattern::compile("sdxl-*", "glob").unwrap();
+        assert!(pattern.matches("sdxl-turbo"));
+        assert!(pattern.matches("sdxl-"));
+        assert!(!pattern.matches("sd-1.5"));
+    }
+
+    #[test]
+    fn test_regex_pattern() {
+        let pattern = ModelPattern::compile("^sd(xl)?-.+$", "regex").unwrap();
+        assert!(pattern.matches("sdxl-turbo"));
+        assert!(pattern.matches("sd-1.5"));
+        assert!(!pattern.matches("dall-e-3"));
+    }
+
+    #[test]
+    fn test_invalid_match_type_rejected() {
+        assert!(ModelPattern::compile("anything", "fuzzy").is_err());
+    }
+
+    #[test]
+    fn test_invalid_regex_rejected() {
+        assert!(ModelPattern::compile("(unclosed", "regex").is_err());
+    }
+}
@@ -11,6 +11,7 @@ pub mod gateway;
 pub mod middleware;
 pub mod queue;
 pub mod response;
+pub mod storage;
 
 pub use error::{AppError, Result};
 
@@ -18,8 +19,10 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use backend::registry::BackendRegistry;
-use gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer};
+use gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer, router::Router};
 use queue::request_queue::RequestQueue;
+use storage::backend::StorageBackend;
+use storage::cache::ImageCache;
 
 /// Application state shared across all handlers
 pub struct AppState {
@@ -27,6 +30,13 @@ pub struct AppState {
     pub backend_registry: Arc<BackendRegistry>,
     pub load_balancer: Arc<LoadBalancer>,
     pub health_manager: Arc<HealthCheckManager>,
+    /// Declarative model-to-backend routing, consulted by `request_queue` for every
+    /// dispatch instead of the load balancer's bare backend-name lookup
+    pub router: Arc<Router>,
     pub request_queue: Arc<RequestQueue>,
+    pub image_cache: Arc<ImageCache>,
+    /// Where generated-image blobs are persisted, selected from `settings.storage.backend`
+    /// at startup so operators can run the gateway statelessly behind many nodes
+    pub storage_backend: Arc<dyn StorageBackend>,
 }
 
@@ -4,8 +4,12 @@ use generative_img_serving::{
     api,
     backend::registry::BackendRegistry,
     config::Settings,
-    gateway::{health_check::HealthCheckManager, load_balancer::LoadBalancer},
-    queue::request_queue::RequestQueue,
+    gateway::{
+        health_check::HealthCheckManager, load_balancer::LoadBalancer, module::build_module_chain,
+        router::Router,
+    },
+    queue::request_queue::{QueueConfig, RequestQueue},
+    storage::{backend::build_storage_backend, cache::ImageCache},
     AppState,
 };
 use std::sync::Arc;
@@ -16,9 +20,8 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize logging
-    let filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info"));
-    
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
     tracing_subscriber::registry()
         .with(filter)
         .with(fmt::layer().json())
@@ -34,42 +37,116 @@ async fn main() -> anyhow::Result<()> {
     );
 
     let settings = Arc::new(RwLock::new(settings));
-    
+
     // Initialize backend registry
     let backend_registry = Arc::new(BackendRegistry::new());
-    
+
     // Register backends from configuration
     {
         let config = settings.read().await;
-        backend_registry.initialize_from_config(&config.backends).await?;
+        backend_registry
+            .initialize_from_config(&config.backends)
+            .await?;
     }
-    
+
     // Initialize load balancer
     let load_balancer = Arc::new(LoadBalancer::new(backend_registry.clone()));
-    
+
     // Initialize health check manager
     let health_manager = Arc::new(HealthCheckManager::new(backend_registry.clone()));
-    
+
     // Start health check background task
     {
         let config = settings.read().await;
-        health_manager.start(config.backends.iter()
-            .map(|b| b.health_check_interval_secs)
-            .min()
-            .unwrap_or(30))
+        health_manager
+            .start(
+                config
+                    .backends
+                    .iter()
+                    .map(|b| b.health_check_interval_secs)
+                    .min()
+                    .unwrap_or(30),
+            )
             .await;
     }
-    
-    // Initialize request queue
-    let request_queue = Arc::new(RequestQueue::new(load_balancer.clone()));
-    
+
+    // Initialize the declarative model-to-backend router, loading its routing table
+    // from `settings.routing` so it's the one consulted for every dispatch
+    let router = {
+        let config = settings.read().await;
+        Arc::new(Router::from_settings(
+            backend_registry.clone(),
+            health_manager.clone(),
+            load_balancer.clone(),
+            &config,
+        ))
+    };
+
+    // Initialize request queue, wiring the configured gateway module (filter) chain
+    let module_chain = {
+        let config = settings.read().await;
+        Arc::new(build_module_chain(&config.modules, None))
+    };
+    let request_queue = Arc::new(RequestQueue::with_modules(
+        load_balancer.clone(),
+        router.clone(),
+        QueueConfig::default(),
+        module_chain,
+        Some(health_manager.clone()),
+    ));
+
+    // Initialize the content-addressable image cache
+    let image_cache = {
+        let config = settings.read().await;
+        Arc::new(ImageCache::new(&config.storage))
+    };
+
+    // Select the storage backend generated images are persisted to
+    let storage_backend = {
+        let config = settings.read().await;
+        build_storage_backend(&config.storage).await?
+    };
+
+    // Reconstruct the backend's in-memory LRU/dedup bookkeeping from what's already on
+    // disk, so a restart doesn't reset capacity-based eviction to a clean slate
+    storage_backend.warm().await?;
+
+    // Periodically age out stale blobs and evict down to the configured byte ceiling
+    {
+        let storage_backend = storage_backend.clone();
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            loop {
+                let (interval_secs, max_age_secs, max_bytes) = {
+                    let config = settings.read().await;
+                    (
+                        config.storage.cleanup_interval_secs,
+                        config.storage.cleanup_max_age_secs,
+                        config.storage.max_bytes,
+                    )
+                };
+                tokio::time::sleep(std::time::Duration::from_secs(interval_secs)).await;
+
+                if let Err(err) = storage_backend.cleanup(max_age_secs).await {
+                    tracing::warn!(error = %err, "Storage age-based cleanup pass failed");
+                }
+                if let Err(err) = storage_backend.cleanup_to_capacity(max_bytes).await {
+                    tracing::warn!(error = %err, "Storage capacity-based cleanup pass failed");
+                }
+            }
+        });
+    }
+
     // Create application state
     let app_state = Arc::new(AppState {
         settings: settings.clone(),
         backend_registry,
         load_balancer,
         health_manager,
+        router,
         request_queue,
+        image_cache,
+        storage_backend,
     });
 
     // Build the router
@@ -80,13 +157,16 @@ async fn main() -> anyhow::Result<()> {
         let config = settings.read().await;
         format!("{}:{}", config.server.host, config.server.port)
     };
-    
+
     info!("Server listening on {}", addr);
-    
+
     // Start the server
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
-
@@ -0,0 +1,191 @@
+//! API authentication middleware
+//!
+//! Authentication is pluggable behind the `ApiAuth` trait: `AuthLayer` holds an
+//! `Arc<dyn ApiAuth>` rather than baking in a single scheme, so a deployment can swap
+//! `StaticKeyAuth` for `TokenIntrospectionAuth` (or a custom scheme) without touching
+//! the middleware itself. The resolved `Principal` is inserted into request extensions
+//! for downstream handlers and the keyed rate limiter to read.
+
+mod static_key;
+mod token_introspection;
+mod traits;
+
+pub use static_key::StaticKeyAuth;
+pub use token_introspection::TokenIntrospectionAuth;
+pub use traits::{ApiAuth, Principal};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header::AUTHORIZATION, Request},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use std::{
+    mem,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Authentication layer
+#[derive(Clone)]
+pub struct AuthLayer {
+    auth: Arc<dyn ApiAuth>,
+    bypass_signed_images: bool,
+}
+
+impl AuthLayer {
+    pub fn new(auth: Arc<dyn ApiAuth>) -> Self {
+        Self::with_signed_image_bypass(auth, false)
+    }
+
+    /// Create an auth layer that additionally lets `/images/*` requests through
+    /// unauthenticated, because they carry their own signed access token instead
+    pub fn with_signed_image_bypass(auth: Arc<dyn ApiAuth>, bypass_signed_images: bool) -> Self {
+        Self {
+            auth,
+            bypass_signed_images,
+        }
+    }
+
+    /// Convenience constructor matching the gateway's original hardcoded static-API-key
+    /// behavior
+    pub fn with_api_keys(api_keys: Vec<String>) -> Self {
+        Self::new(Arc::new(StaticKeyAuth::new(api_keys)))
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthMiddleware {
+            inner,
+            auth: self.auth.clone(),
+            bypass_signed_images: self.bypass_signed_images,
+        }
+    }
+}
+
+/// Authentication middleware service
+#[derive(Clone)]
+pub struct AuthMiddleware<S> {
+    inner: S,
+    auth: Arc<dyn ApiAuth>,
+    bypass_signed_images: bool,
+}
+
+impl<S> Service<Request<Body>> for AuthMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + Clone + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        // Skip authentication for health check, metrics, and (when enabled) signed image URLs
+        let path = request.uri().path();
+        if path == "/health"
+            || path == "/metrics"
+            || (self.bypass_signed_images && path.starts_with("/images/"))
+        {
+            let future = self.inner.call(request);
+            return Box::pin(async move { future.await });
+        }
+
+        let auth = self.auth.clone();
+        // `authenticate` is async (token-introspection impls make a network call), so
+        // the inner service has to move into the boxed future rather than being called
+        // synchronously here; swap in a clone and let the moved-out service handle it
+        let clone = self.inner.clone();
+        let mut inner = mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            match auth.authenticate(request.headers()).await {
+                Ok(principal) => {
+                    request.extensions_mut().insert(principal);
+                    inner.call(request).await
+                }
+                Err(e) => Ok(e.into_response()),
+            }
+        })
+    }
+}
+
+/// Extract the bearer (or raw) API key from an `Authorization` header, if present.
+/// Shared with `static_key`/`token_introspection` and with `rate_limit` so per-key
+/// buckets key on the same value auth validates.
+pub(crate) fn extract_api_key(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.trim_start_matches("Bearer ").to_string())
+}
+
+/// Resolve the client's IP address: prefer a proxy-supplied `X-Forwarded-For` (using the
+/// first, left-most hop, which is the original client) or `X-Real-IP` header, falling back
+/// to the connection's own socket address when the gateway is reached directly. Shared with
+/// `rate_limit` so unauthenticated clients are bucketed by their real IP even behind a proxy.
+pub(crate) fn extract_client_ip(request: &Request<Body>) -> Option<IpAddr> {
+    if let Some(ip) = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(',').next())
+        .and_then(|first| first.trim().parse::<IpAddr>().ok())
+    {
+        return Some(ip);
+    }
+
+    if let Some(ip) = request
+        .headers()
+        .get("x-real-ip")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.trim().parse::<IpAddr>().ok())
+    {
+        return Some(ip);
+    }
+
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_client_ip_prefers_forwarded_for() {
+        let request = Request::builder()
+            .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+            .header("x-real-ip", "198.51.100.1")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            extract_client_ip(&request),
+            Some("203.0.113.7".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_extract_client_ip_falls_back_to_real_ip() {
+        let request = Request::builder()
+            .header("x-real-ip", "198.51.100.1")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            extract_client_ip(&request),
+            Some("198.51.100.1".parse().unwrap())
+        );
+    }
+}
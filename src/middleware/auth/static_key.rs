@@ -0,0 +1,50 @@
+//! Static API key matching, the default `ApiAuth` implementation
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use std::collections::HashSet;
+
+use super::extract_api_key;
+use super::traits::{ApiAuth, Principal};
+use crate::error::{AppError, Result};
+
+/// Authenticates requests by comparing their bearer token against a fixed set of
+/// configured API keys. The key itself becomes the resolved principal's subject.
+pub struct StaticKeyAuth {
+    api_keys: HashSet<String>,
+}
+
+impl StaticKeyAuth {
+    pub fn new(api_keys: Vec<String>) -> Self {
+        Self {
+            api_keys: api_keys.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl ApiAuth for StaticKeyAuth {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Principal> {
+        // If no API keys are configured, allow all requests
+        if self.api_keys.is_empty() {
+            return Ok(Principal {
+                subject: "anonymous".to_string(),
+                scopes: vec![],
+            });
+        }
+
+        match extract_api_key(headers) {
+            Some(key) if self.api_keys.contains(&key) => Ok(Principal {
+                subject: key,
+                scopes: vec![],
+            }),
+            Some(_) => Err(AppError::AuthenticationFailed(
+                "Invalid API key".to_string(),
+            )),
+            None => Err(AppError::AuthenticationFailed(
+                "API key required. Provide via Authorization header: 'Bearer YOUR_API_KEY'"
+                    .to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! OAuth/IndieAuth-style token introspection auth, for deployments that front the
+//! gateway with a real identity provider instead of a fixed list of API keys
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use super::extract_api_key;
+use super::traits::{ApiAuth, Principal};
+use crate::error::{AppError, Result};
+
+/// Response shape of an RFC 7662-style token introspection endpoint
+#[derive(Debug, Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+    #[serde(default)]
+    sub: Option<String>,
+    /// Space-separated scope list, per RFC 7662
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+struct CachedPrincipal {
+    principal: Principal,
+    cached_at: Instant,
+}
+
+/// Authenticates requests by POSTing the presented bearer token to a configured
+/// introspection endpoint and caching the returned subject/scopes for a TTL, so a
+/// hot path doesn't round-trip to the identity provider on every request
+pub struct TokenIntrospectionAuth {
+    client: reqwest::Client,
+    introspection_url: String,
+    cache: DashMap<String, CachedPrincipal>,
+    cache_ttl: Duration,
+}
+
+impl TokenIntrospectionAuth {
+    pub fn new(introspection_url: String, cache_ttl: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            introspection_url,
+            cache: DashMap::new(),
+            cache_ttl,
+        }
+    }
+}
+
+#[async_trait]
+impl ApiAuth for TokenIntrospectionAuth {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Principal> {
+        let token = extract_api_key(headers)
+            .ok_or_else(|| AppError::AuthenticationFailed("Bearer token required".to_string()))?;
+
+        if let Some(cached) = self.cache.get(&token) {
+            if cached.cached_at.elapsed() < self.cache_ttl {
+                return Ok(cached.principal.clone());
+            }
+        }
+
+        let response = self
+            .client
+            .post(&self.introspection_url)
+            .form(&[("token", token.as_str())])
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::AuthenticationFailed(format!("Token introspection request failed: {}", e))
+            })?;
+
+        let body: IntrospectionResponse = response.json().await.map_err(|e| {
+            AppError::AuthenticationFailed(format!("Invalid introspection response: {}", e))
+        })?;
+
+        if !body.active {
+            return Err(AppError::AuthenticationFailed(
+                "Token is not active".to_string(),
+            ));
+        }
+
+        let principal = Principal {
+            subject: body.sub.unwrap_or_else(|| token.clone()),
+            scopes: body
+                .scope
+                .map(|s| s.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+        };
+
+        self.cache.insert(
+            token,
+            CachedPrincipal {
+                principal: principal.clone(),
+                cached_at: Instant::now(),
+            },
+        );
+
+        Ok(principal)
+    }
+}
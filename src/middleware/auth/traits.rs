@@ -0,0 +1,27 @@
+//! The pluggable authentication trait and the identity it resolves
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+
+use crate::error::Result;
+
+/// The authenticated identity resolved from a request's credentials, inserted into
+/// request extensions so downstream handlers and the keyed rate limiter can read it
+/// without re-deriving it themselves
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// Stable identifier for this caller: the API key itself for `StaticKeyAuth`, or
+    /// the introspected token's `sub` claim for `TokenIntrospectionAuth`
+    pub subject: String,
+    /// Scopes/permissions granted to this principal, if the auth scheme has a notion
+    /// of them. Empty when the scheme doesn't distinguish scopes.
+    pub scopes: Vec<String>,
+}
+
+/// A pluggable request authentication scheme. Implementations inspect the request's
+/// headers and either resolve a `Principal` or fail with `AppError::AuthenticationFailed`.
+#[async_trait]
+pub trait ApiAuth: Send + Sync {
+    /// Authenticate a request from its headers, returning the resolved principal
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Principal>;
+}
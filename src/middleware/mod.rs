@@ -0,0 +1,5 @@
+//! Middleware module - authentication, rate limiting, and signed URL tokens
+
+pub mod auth;
+pub mod rate_limit;
+pub mod signed_url;
@@ -1,20 +1,35 @@
 //! Rate limiting middleware using the Governor crate
+//!
+//! Each client gets its own token bucket, keyed on its API key (falling back to its
+//! real IP address for unauthenticated routes) so one noisy client can't burn through
+//! another's budget. Authenticated and anonymous clients default to separate quotas,
+//! since an anonymous bucket is usually shared by far more distinct real clients
+//! (everyone behind the same NAT or proxy) than a single API key is. Per-key overrides
+//! remain configurable via `AuthConfig.rate_limit_overrides`.
+//!
+//! Buckets are individually-quota'd `RateLimiter<NotKeyed, ...>` instances behind a
+//! `DashMap`, rather than a single Governor `RateLimiter<String, DefaultKeyedStateStore<String>,
+//! ...>`. A shared keyed limiter only supports one quota for every key it manages, which
+//! would drop per-key overrides on the floor; this gets the same per-key isolation while
+//! keeping heterogeneous quota tiers.
 
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    http::{header::HeaderName, HeaderValue, Request, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use dashmap::DashMap;
 use futures::future::BoxFuture;
 use governor::{
-    clock::DefaultClock,
+    clock::{Clock, DefaultClock},
     middleware::NoOpMiddleware,
     state::{InMemoryState, NotKeyed},
     Quota, RateLimiter,
 };
 use serde::Serialize;
 use std::{
+    collections::HashMap,
     num::NonZeroU32,
     sync::Arc,
     task::{Context, Poll},
@@ -22,6 +37,9 @@ use std::{
 use tower::{Layer, Service};
 use tracing::warn;
 
+use crate::config::KeyRateLimit;
+use crate::middleware::auth::{extract_api_key, extract_client_ip, Principal};
+
 /// Rate limit error response
 #[derive(Serialize)]
 struct RateLimitError {
@@ -35,22 +53,102 @@ struct RateLimitErrorDetail {
     code: String,
 }
 
-type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>>;
+type KeyRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>;
+
+/// A caller-supplied function that derives a rate-limit bucket key from a request,
+/// for operators who want to key on something other than the built-in API-key-or-IP
+/// scheme (a tenant header, say)
+type KeyExtractor = Arc<dyn Fn(&Request<Body>) -> String + Send + Sync>;
+
+static RETRY_AFTER: HeaderName = HeaderName::from_static("retry-after");
+static RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+static RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+static RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// The client's rate-limit identity, used to pick both the bucket key and the default
+/// quota tier (authenticated keys and anonymous IPs default to different quotas)
+enum ClientIdentity {
+    /// Authenticated via API key; overrides are looked up by the raw key
+    ApiKey(String),
+    /// No (or bypassed) authentication; bucketed by client IP
+    Ip(String),
+    /// A custom `key_extractor` produced this bucket key directly; treated like an
+    /// authenticated client for quota purposes, since the operator owns the tiering
+    Custom(String),
+}
+
+impl ClientIdentity {
+    fn bucket_key(&self) -> String {
+        match self {
+            ClientIdentity::ApiKey(key) => format!("key:{}", key),
+            ClientIdentity::Ip(ip) => format!("ip:{}", ip),
+            ClientIdentity::Custom(key) => format!("custom:{}", key),
+        }
+    }
+}
 
 /// Rate limiting layer
 #[derive(Clone)]
 pub struct RateLimitLayer {
-    limiter: SharedRateLimiter,
+    authenticated_requests_per_second: u32,
+    authenticated_burst_size: u32,
+    anonymous_requests_per_second: u32,
+    anonymous_burst_size: u32,
+    overrides: Arc<HashMap<String, KeyRateLimit>>,
+    buckets: Arc<DashMap<String, Arc<KeyRateLimiter>>>,
+    key_extractor: Option<KeyExtractor>,
 }
 
 impl RateLimitLayer {
+    /// Create a layer with a single default quota shared by authenticated and
+    /// anonymous clients alike
     pub fn new(requests_per_second: u32, burst_size: u32) -> Self {
-        let quota = Quota::per_second(NonZeroU32::new(requests_per_second).unwrap_or(NonZeroU32::new(100).unwrap()))
-            .allow_burst(NonZeroU32::new(burst_size).unwrap_or(NonZeroU32::new(200).unwrap()));
-        
-        let limiter = Arc::new(RateLimiter::direct(quota));
-        
-        Self { limiter }
+        Self::with_overrides(requests_per_second, burst_size, HashMap::new())
+    }
+
+    /// Create a layer with a default quota and per-API-key overrides
+    pub fn with_overrides(
+        requests_per_second: u32,
+        burst_size: u32,
+        overrides: HashMap<String, KeyRateLimit>,
+    ) -> Self {
+        Self {
+            authenticated_requests_per_second: requests_per_second,
+            authenticated_burst_size: burst_size,
+            anonymous_requests_per_second: requests_per_second,
+            anonymous_burst_size: burst_size,
+            overrides: Arc::new(overrides),
+            buckets: Arc::new(DashMap::new()),
+            key_extractor: None,
+        }
+    }
+
+    /// Give anonymous (IP-keyed) clients their own default quota instead of sharing the
+    /// authenticated default
+    pub fn with_anonymous_quota(mut self, requests_per_second: u32, burst_size: u32) -> Self {
+        self.anonymous_requests_per_second = requests_per_second;
+        self.anonymous_burst_size = burst_size;
+        self
+    }
+
+    /// Create a layer that buckets clients by a caller-supplied key instead of the
+    /// built-in API-key-or-IP scheme, so operators can route on whatever distinguishes
+    /// their tenants (a header, a path segment, ...). Per-API-key overrides don't apply
+    /// under a custom extractor, since those are keyed by the raw API key specifically.
+    pub fn keyed(
+        requests_per_second: u32,
+        burst_size: u32,
+        key_extractor: impl Fn(&Request<Body>) -> String + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            authenticated_requests_per_second: requests_per_second,
+            authenticated_burst_size: burst_size,
+            anonymous_requests_per_second: requests_per_second,
+            anonymous_burst_size: burst_size,
+            overrides: Arc::new(HashMap::new()),
+            buckets: Arc::new(DashMap::new()),
+            key_extractor: Some(Arc::new(key_extractor)),
+        }
     }
 }
 
@@ -60,7 +158,13 @@ impl<S> Layer<S> for RateLimitLayer {
     fn layer(&self, inner: S) -> Self::Service {
         RateLimitMiddleware {
             inner,
-            limiter: self.limiter.clone(),
+            authenticated_requests_per_second: self.authenticated_requests_per_second,
+            authenticated_burst_size: self.authenticated_burst_size,
+            anonymous_requests_per_second: self.anonymous_requests_per_second,
+            anonymous_burst_size: self.anonymous_burst_size,
+            overrides: self.overrides.clone(),
+            buckets: self.buckets.clone(),
+            key_extractor: self.key_extractor.clone(),
         }
     }
 }
@@ -69,7 +173,71 @@ impl<S> Layer<S> for RateLimitLayer {
 #[derive(Clone)]
 pub struct RateLimitMiddleware<S> {
     inner: S,
-    limiter: SharedRateLimiter,
+    authenticated_requests_per_second: u32,
+    authenticated_burst_size: u32,
+    anonymous_requests_per_second: u32,
+    anonymous_burst_size: u32,
+    overrides: Arc<HashMap<String, KeyRateLimit>>,
+    buckets: Arc<DashMap<String, Arc<KeyRateLimiter>>>,
+    key_extractor: Option<KeyExtractor>,
+}
+
+impl<S> RateLimitMiddleware<S> {
+    /// The client's rate-limit identity: a custom `key_extractor`'s result if one is
+    /// configured, else the `Principal` the auth middleware already resolved (so both
+    /// middlewares agree on who a request came from), else its raw API key, else its
+    /// real IP address
+    fn client_identity(&self, request: &Request<Body>) -> ClientIdentity {
+        if let Some(extractor) = &self.key_extractor {
+            return ClientIdentity::Custom(extractor(request));
+        }
+        if let Some(principal) = request.extensions().get::<Principal>() {
+            return ClientIdentity::ApiKey(principal.subject.clone());
+        }
+        if let Some(api_key) = extract_api_key(request.headers()) {
+            return ClientIdentity::ApiKey(api_key);
+        }
+        match extract_client_ip(request) {
+            Some(ip) => ClientIdentity::Ip(ip.to_string()),
+            None => ClientIdentity::Ip("unknown".to_string()),
+        }
+    }
+
+    /// Quota configured for this client: a per-API-key override if one applies,
+    /// otherwise the anonymous or authenticated default depending on identity
+    fn quota_for(&self, identity: &ClientIdentity) -> (u32, u32) {
+        if let ClientIdentity::ApiKey(key) = identity {
+            if let Some(over) = self.overrides.get(key) {
+                return (over.requests_per_second, over.burst_size);
+            }
+        }
+
+        match identity {
+            ClientIdentity::Ip(_) => (
+                self.anonymous_requests_per_second,
+                self.anonymous_burst_size,
+            ),
+            ClientIdentity::ApiKey(_) | ClientIdentity::Custom(_) => (
+                self.authenticated_requests_per_second,
+                self.authenticated_burst_size,
+            ),
+        }
+    }
+
+    /// Fetch or lazily create this client's token bucket
+    fn bucket_for(&self, bucket_key: &str, quota: (u32, u32)) -> Arc<KeyRateLimiter> {
+        if let Some(limiter) = self.buckets.get(bucket_key) {
+            return limiter.clone();
+        }
+
+        let (rps, burst) = quota;
+        let quota =
+            Quota::per_second(NonZeroU32::new(rps).unwrap_or(NonZeroU32::new(100).unwrap()))
+                .allow_burst(NonZeroU32::new(burst).unwrap_or(NonZeroU32::new(200).unwrap()));
+        let limiter = Arc::new(RateLimiter::direct(quota));
+        self.buckets.insert(bucket_key.to_string(), limiter.clone());
+        limiter
+    }
 }
 
 impl<S> Service<Request<Body>> for RateLimitMiddleware<S>
@@ -93,23 +261,30 @@ where
             return Box::pin(async move { future.await });
         }
 
-        // Check rate limit
-        match self.limiter.check() {
+        let identity = self.client_identity(&request);
+        let bucket_key = identity.bucket_key();
+        let quota @ (_, burst) = self.quota_for(&identity);
+        let limiter = self.bucket_for(&bucket_key, quota);
+
+        match limiter.check() {
             Ok(_) => {
                 let future = self.inner.call(request);
                 Box::pin(async move { future.await })
             }
-            Err(_) => {
-                warn!("Rate limit exceeded");
-                Box::pin(async move {
-                    Ok(create_rate_limit_error_response())
-                })
+            Err(not_until) => {
+                // `not_until` pins down exactly when the bucket will admit another
+                // request; derive both Retry-After and X-RateLimit-Reset from it
+                // instead of discarding it after a single `wait_time_from` call.
+                let now = DefaultClock::default().now();
+                let retry_after = not_until.wait_time_from(now);
+                warn!(key = %bucket_key, "Rate limit exceeded");
+                Box::pin(async move { Ok(create_rate_limit_error_response(retry_after, burst)) })
             }
         }
     }
 }
 
-fn create_rate_limit_error_response() -> Response {
+fn create_rate_limit_error_response(retry_after: std::time::Duration, limit: u32) -> Response {
     let error = RateLimitError {
         error: RateLimitErrorDetail {
             message: "Rate limit exceeded. Please slow down your requests.".to_string(),
@@ -117,8 +292,34 @@ fn create_rate_limit_error_response() -> Response {
             code: "rate_limit_exceeded".to_string(),
         },
     };
-    
-    (StatusCode::TOO_MANY_REQUESTS, Json(error)).into_response()
+
+    let retry_after_secs = retry_after.as_secs().max(1);
+    let reset_unix = unix_now_secs() + retry_after_secs;
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(error)).into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        RETRY_AFTER.clone(),
+        HeaderValue::from_str(&retry_after_secs.to_string()).unwrap(),
+    );
+    headers.insert(
+        RATE_LIMIT_LIMIT.clone(),
+        HeaderValue::from_str(&limit.to_string()).unwrap(),
+    );
+    // The limiter rejected this request outright, so by definition no tokens remain
+    // in the bucket right now.
+    headers.insert(RATE_LIMIT_REMAINING.clone(), HeaderValue::from_static("0"));
+    headers.insert(
+        RATE_LIMIT_RESET.clone(),
+        HeaderValue::from_str(&reset_unix.to_string()).unwrap(),
+    );
+    response
+}
+
+fn unix_now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -128,8 +329,39 @@ mod tests {
     #[test]
     fn test_rate_limit_layer_creation() {
         let layer = RateLimitLayer::new(100, 200);
-        // Should not panic
-        assert!(layer.limiter.check().is_ok());
+        assert_eq!(layer.authenticated_requests_per_second, 100);
+        assert_eq!(layer.authenticated_burst_size, 200);
+        assert_eq!(layer.anonymous_requests_per_second, 100);
+    }
+
+    #[test]
+    fn test_with_anonymous_quota_overrides_anonymous_default_only() {
+        let layer = RateLimitLayer::new(100, 200).with_anonymous_quota(10, 20);
+        assert_eq!(layer.authenticated_requests_per_second, 100);
+        assert_eq!(layer.anonymous_requests_per_second, 10);
+        assert_eq!(layer.anonymous_burst_size, 20);
     }
-}
 
+    #[test]
+    fn test_error_response_carries_rate_limit_headers() {
+        let response = create_rate_limit_error_response(std::time::Duration::from_secs(5), 100);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let headers = response.headers();
+        assert_eq!(headers.get(&RETRY_AFTER).unwrap(), "5");
+        assert_eq!(headers.get(&RATE_LIMIT_LIMIT).unwrap(), "100");
+        assert_eq!(headers.get(&RATE_LIMIT_REMAINING).unwrap(), "0");
+        assert!(headers.get(&RATE_LIMIT_RESET).is_some());
+    }
+
+    #[test]
+    fn test_keyed_layer_uses_custom_extractor() {
+        let layer = RateLimitLayer::keyed(5, 10, |req: &Request<Body>| {
+            req.headers()
+                .get("x-tenant")
+                .and_then(|h| h.to_str().ok())
+                .unwrap_or("default")
+                .to_string()
+        });
+        assert!(layer.key_extractor.is_some());
+    }
+}
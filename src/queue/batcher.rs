@@ -1,12 +1,77 @@
-//! Dynamic batch processor for grouping requests
+//! Continuous batching processor that groups shape-compatible requests
 
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{oneshot, Mutex};
-use tracing::{debug, info};
+use tokio::sync::{oneshot, Mutex, Notify, OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info, warn};
+
+use crate::backend::traits::{GenerateRequest, GenerateResponse, ImageBackend};
+use crate::error::{AppError, Result};
+
+/// Error delivered to a caller waiting on a batched result. Wraps `AppError` in an `Arc`
+/// so the exact same failure can be cloned out to every pending `response_tx` (and to any
+/// `add_request` call that arrives afterward) when the batch worker itself dies, instead
+/// of each caller separately reconstructing (or losing) the cause. Modeled on the shared
+/// "broken pipe" error tower's `Buffer`/`Batch` layers hand back to queued callers.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ServiceError {
+    #[error("{0}")]
+    Failed(Arc<AppError>),
+    #[error("Batch worker is no longer running")]
+    Closed,
+    #[error("Request was shed because the batch queue was full")]
+    Shed,
+}
+
+impl From<ServiceError> for AppError {
+    fn from(error: ServiceError) -> Self {
+        match error {
+            ServiceError::Failed(inner) => AppError::Internal(inner.to_string()),
+            ServiceError::Closed => {
+                AppError::Internal("Batch worker is no longer running".to_string())
+            }
+            ServiceError::Shed => {
+                AppError::QueueFull("Request was shed because the batch queue was full".to_string())
+            }
+        }
+    }
+}
+
+/// How `Batcher::add_request` behaves once `max_concurrent_requests` pending/in-flight
+/// requests are already held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Reject the new request immediately with `AppError::QueueFull`
+    RejectNewest,
+    /// Evict and fail the globally oldest pending request with a shed error, then admit
+    /// the new one
+    DropOldest,
+    /// Wait for capacity to free up, up to `BatchConfig::enqueue_timeout_ms`, before
+    /// giving up with `AppError::QueueFull`
+    Block,
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        Self::RejectNewest
+    }
+}
 
-use crate::backend::traits::{GenerateRequest, GenerateResponse, GeneratedImage, ImageBackend};
-use crate::error::Result;
+/// Snapshot of a batcher's queue depth and lifetime enqueue/dequeue/drop counters, for
+/// the `/metrics` endpoint to report per-backend queue pressure
+#[derive(Debug, Clone, Default)]
+pub struct BatcherStats {
+    /// Requests currently waiting in buckets; requests already handed to the backend
+    /// are not counted
+    pub depth: usize,
+    pub enqueued: u64,
+    pub dequeued: u64,
+    /// Requests shed by `OverflowPolicy::DropOldest` or rejected at the door by
+    /// `OverflowPolicy::RejectNewest`/`OverflowPolicy::Block`
+    pub dropped: u64,
+}
 
 /// Configuration for the batch processor
 #[derive(Debug, Clone)]
@@ -17,6 +82,19 @@ pub struct BatchConfig {
     pub max_wait_ms: u64,
     /// Whether batching is enabled
     pub enabled: bool,
+    /// Maximum number of requests this batcher holds in flight (queued plus being
+    /// processed) at once; once reached, `add_request` applies `overflow_policy`.
+    pub max_concurrent_requests: usize,
+    /// Once `pending / max_batch_size` reaches this ratio, a ready bucket is flushed
+    /// immediately on the next wake-up instead of waiting out `max_wait_ms`, so a backed
+    /// up queue keeps the backend saturated. Modeled on text-generation-inference's
+    /// `waiting_served_ratio`.
+    pub waiting_served_ratio: f32,
+    /// What `add_request` does once `max_concurrent_requests` is already reached
+    pub overflow_policy: OverflowPolicy,
+    /// How long `add_request` waits for capacity under `OverflowPolicy::Block` before
+    /// giving up with `AppError::QueueFull`
+    pub enqueue_timeout_ms: u64,
 }
 
 impl Default for BatchConfig {
@@ -25,21 +103,129 @@ impl Default for BatchConfig {
             max_batch_size: 4,
             max_wait_ms: 100,
             enabled: true,
+            max_concurrent_requests: 64,
+            waiting_served_ratio: 1.2,
+            overflow_policy: OverflowPolicy::RejectNewest,
+            enqueue_timeout_ms: 5000,
         }
     }
 }
 
-/// A request waiting to be batched
+/// Groups requests that the backend can serve in a single forward pass. Two requests
+/// only share a bucket if every field here is identical; `guidance_scale` is compared by
+/// bit pattern since `f32` isn't `Eq`/`Hash`. Deliberately excludes `prompt` and `n`: those
+/// are exactly the two fields `combine_requests`/`split_response` account for when merging
+/// bucket members into one call.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BucketKey {
+    model: Option<String>,
+    negative_prompt: Option<String>,
+    width: u32,
+    height: u32,
+    num_inference_steps: Option<u32>,
+    guidance_scale_bits: Option<u32>,
+}
+
+impl BucketKey {
+    fn from_request(request: &GenerateRequest) -> Self {
+        Self {
+            model: request.model.clone(),
+            negative_prompt: request.negative_prompt.clone(),
+            width: request.width,
+            height: request.height,
+            num_inference_steps: request.num_inference_steps,
+            guidance_scale_bits: request.guidance_scale.map(f32::to_bits),
+        }
+    }
+}
+
+/// Merge requests that agree on every field `BucketKey` covers *and* share the same
+/// `prompt` into one request whose `n` is their combined count, so a single backend call
+/// can serve all of them in one forward pass. `GenerateRequest` only carries one `prompt`,
+/// so unlike `BucketKey`, this merge step does require it to match across the slice.
+fn combine_requests(requests: &[GenerateRequest]) -> GenerateRequest {
+    let mut combined = requests[0].clone();
+    combined.n = requests.iter().map(|r| r.n).sum();
+    combined
+}
+
+/// Slice a combined response's images back out to each caller in the same order
+/// `combine_requests` merged them in, so each caller gets exactly the `n` images it asked
+/// for.
+fn split_response(
+    response: GenerateResponse,
+    requests: &[GenerateRequest],
+) -> Vec<GenerateResponse> {
+    let mut images = response.images.into_iter();
+    requests
+        .iter()
+        .map(|r| GenerateResponse {
+            images: (&mut images).take(r.n as usize).collect(),
+            model: response.model.clone(),
+        })
+        .collect()
+}
+
+/// A request waiting to be batched, holding the concurrency permit it was admitted with
+/// until its result is sent (and the permit drops), so the concurrency limit bounds
+/// requests that are queued *or* being processed, not just queued
 struct BatchedRequest {
     request: GenerateRequest,
-    response_tx: oneshot::Sender<Result<GenerateResponse>>,
+    response_tx: oneshot::Sender<std::result::Result<GenerateResponse, ServiceError>>,
+    permit: OwnedSemaphorePermit,
+    /// When this request was admitted, used by `OverflowPolicy::DropOldest` to find the
+    /// globally oldest pending request across all buckets
+    enqueued_at: Instant,
+}
+
+/// Split a drained batch back into its three parallel columns, keeping each request's
+/// permit alive past this call so it isn't released until the caller is done with it
+fn unzip_batch(
+    batch: Vec<BatchedRequest>,
+) -> (
+    Vec<GenerateRequest>,
+    Vec<oneshot::Sender<std::result::Result<GenerateResponse, ServiceError>>>,
+    Vec<OwnedSemaphorePermit>,
+) {
+    let mut requests = Vec::with_capacity(batch.len());
+    let mut responders = Vec::with_capacity(batch.len());
+    let mut permits = Vec::with_capacity(batch.len());
+    for batched in batch {
+        requests.push(batched.request);
+        responders.push(batched.response_tx);
+        permits.push(batched.permit);
+    }
+    (requests, responders, permits)
 }
 
-/// Batch processor for grouping multiple requests
+/// Requests accumulated for one `BucketKey`, along with when the first of them arrived
+struct Bucket {
+    requests: Vec<BatchedRequest>,
+    oldest: Instant,
+}
+
+/// Continuous-batching processor: requests are grouped into per-shape buckets and a
+/// bucket is flushed once it reaches `max_batch_size` or its oldest member has waited
+/// `max_wait_ms`. Backends that advertise `supports_batching()` get same-prompt requests
+/// within the bucket merged into a single combined `generate` call; others fall back to
+/// `generate_batch`'s one-by-one dispatch.
 pub struct Batcher {
     config: BatchConfig,
-    pending_requests: Arc<Mutex<Vec<BatchedRequest>>>,
-    last_batch_time: Arc<Mutex<Instant>>,
+    buckets: Arc<Mutex<HashMap<BucketKey, Bucket>>>,
+    /// Bounds the number of requests held in flight (queued or processing) at once
+    permits: Arc<Semaphore>,
+    /// Wakes the flush loop as soon as a request arrives instead of it busy-polling
+    notify: Notify,
+    /// Set once the flush loop has died (panicked or exited); from then on every pending
+    /// and future caller is handed this same cloned error instead of a silently dropped
+    /// channel
+    terminal_error: Mutex<Option<ServiceError>>,
+    /// Lifetime count of requests admitted via `add_request`
+    enqueued: AtomicU64,
+    /// Lifetime count of requests handed to the backend in `process_ready_batches`
+    dequeued: AtomicU64,
+    /// Lifetime count of requests shed or rejected by `overflow_policy`
+    dropped: AtomicU64,
 }
 
 impl Batcher {
@@ -50,130 +236,311 @@ impl Batcher {
 
     /// Create a new batcher with custom configuration
     pub fn with_config(config: BatchConfig) -> Self {
+        let permits = Arc::new(Semaphore::new(config.max_concurrent_requests));
         Self {
             config,
-            pending_requests: Arc::new(Mutex::new(Vec::new())),
-            last_batch_time: Arc::new(Mutex::new(Instant::now())),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            permits,
+            notify: Notify::new(),
+            terminal_error: Mutex::new(None),
+            enqueued: AtomicU64::new(0),
+            dequeued: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
         }
     }
 
-    /// Add a request to the batch
-    pub async fn add_request(&self, request: GenerateRequest) -> oneshot::Receiver<Result<GenerateResponse>> {
+    /// Add a request to its bucket, creating the bucket if this is the first request of
+    /// its shape, and wake the flush loop. If batching is disabled the receiver is
+    /// returned unfulfilled; callers are expected to process the request directly in that
+    /// case. Once `max_concurrent_requests` are already in flight, `overflow_policy`
+    /// decides whether this call is rejected outright, evicts the oldest pending request
+    /// to make room, or waits for capacity. Also rejected once the flush loop has died
+    /// and recorded a terminal error.
+    pub async fn add_request(
+        &self,
+        request: GenerateRequest,
+    ) -> Result<oneshot::Receiver<std::result::Result<GenerateResponse, ServiceError>>> {
         let (response_tx, response_rx) = oneshot::channel();
 
         if !self.config.enabled {
-            // If batching is disabled, return immediately
-            // The caller will process the request directly
-            return response_rx;
+            return Ok(response_rx);
         }
 
+        if let Some(error) = self.terminal_error.lock().await.clone() {
+            return Err(error.into());
+        }
+
+        let permit = self.acquire_permit().await?;
+
+        let key = BucketKey::from_request(&request);
         let batched = BatchedRequest {
             request,
             response_tx,
+            permit,
+            enqueued_at: Instant::now(),
         };
 
-        let mut pending = self.pending_requests.lock().await;
-        pending.push(batched);
-
-        response_rx
+        let mut buckets = self.buckets.lock().await;
+        buckets
+            .entry(key)
+            .or_insert_with(|| Bucket {
+                requests: Vec::new(),
+                oldest: Instant::now(),
+            })
+            .requests
+            .push(batched);
+        drop(buckets);
+
+        self.enqueued.fetch_add(1, Ordering::Relaxed);
+        self.notify.notify_one();
+
+        Ok(response_rx)
     }
 
-    /// Check if the batch should be processed
-    pub async fn should_process(&self) -> bool {
-        let pending = self.pending_requests.lock().await;
-        let last_time = self.last_batch_time.lock().await;
+    /// Acquire a concurrency permit per `overflow_policy`, applying backpressure or
+    /// shedding load once the batcher is already at `max_concurrent_requests`
+    async fn acquire_permit(&self) -> Result<OwnedSemaphorePermit> {
+        match self.config.overflow_policy {
+            OverflowPolicy::RejectNewest => {
+                self.permits.clone().try_acquire_owned().map_err(|_| {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    AppError::QueueFull(
+                        "Batch queue is full; too many requests in flight".to_string(),
+                    )
+                })
+            }
+            OverflowPolicy::Block => {
+                let timeout = Duration::from_millis(self.config.enqueue_timeout_ms);
+                match tokio::time::timeout(timeout, self.permits.clone().acquire_owned()).await {
+                    Ok(Ok(permit)) => Ok(permit),
+                    Ok(Err(_)) => Err(AppError::Internal(
+                        "Batch concurrency semaphore was closed".to_string(),
+                    )),
+                    Err(_) => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        Err(AppError::QueueFull(
+                            "Timed out waiting for batch queue capacity".to_string(),
+                        ))
+                    }
+                }
+            }
+            OverflowPolicy::DropOldest => {
+                if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+                    return Ok(permit);
+                }
 
-        if pending.is_empty() {
-            return false;
-        }
+                self.evict_oldest().await;
 
-        // Process if batch is full
-        if pending.len() >= self.config.max_batch_size {
-            return true;
+                self.permits.clone().try_acquire_owned().map_err(|_| {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    AppError::QueueFull(
+                        "Batch queue is full and had nothing left to evict".to_string(),
+                    )
+                })
+            }
         }
+    }
+
+    /// Evict and fail the globally oldest pending request (the earliest-enqueued first
+    /// element across all buckets) with `ServiceError::Shed`, freeing its permit for the
+    /// request that triggered the eviction
+    async fn evict_oldest(&self) {
+        let evicted = {
+            let mut buckets = self.buckets.lock().await;
+            let oldest_key = buckets
+                .iter()
+                .filter_map(|(key, bucket)| {
+                    bucket
+                        .requests
+                        .first()
+                        .map(|r| (key.clone(), r.enqueued_at))
+                })
+                .min_by_key(|(_, enqueued_at)| *enqueued_at)
+                .map(|(key, _)| key);
+
+            oldest_key.and_then(|key| {
+                let bucket = buckets.get_mut(&key)?;
+                (!bucket.requests.is_empty()).then(|| bucket.requests.remove(0))
+            })
+        };
 
-        // Process if max wait time exceeded
-        if last_time.elapsed() >= Duration::from_millis(self.config.max_wait_ms) {
-            return true;
+        if let Some(evicted) = evicted {
+            let _ = evicted.response_tx.send(Err(ServiceError::Shed));
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            warn!("Evicted oldest pending request to make room under DropOldest overflow policy");
+            drop(evicted.permit);
         }
+    }
 
-        false
+    /// Wait until either a request arrives or `max_wait_ms` elapses, whichever comes
+    /// first, so the flush loop reacts immediately under load instead of polling on a
+    /// fixed interval
+    pub async fn wait_for_work(&self) {
+        let max_wait = Duration::from_millis(self.config.max_wait_ms);
+        let _ = tokio::time::timeout(max_wait, self.notify.notified()).await;
     }
 
-    /// Process the current batch
-    pub async fn process_batch<B: ImageBackend + ?Sized>(&self, backend: &B) -> Result<()> {
-        let mut pending = self.pending_requests.lock().await;
-        
-        if pending.is_empty() {
-            return Ok(());
-        }
+    /// Check if any bucket is ready to be flushed
+    pub async fn should_process(&self) -> bool {
+        let buckets = self.buckets.lock().await;
+        let max_wait = Duration::from_millis(self.config.max_wait_ms);
+        let backed_up = self.is_backed_up(&buckets);
+        buckets
+            .values()
+            .any(|bucket| self.bucket_ready(bucket, max_wait, backed_up))
+    }
+
+    /// Whether the total pending backlog is large enough, relative to `max_batch_size`,
+    /// that ready buckets should be flushed right away instead of waiting for more of
+    /// `max_wait_ms` to elapse
+    fn is_backed_up(&self, buckets: &HashMap<BucketKey, Bucket>) -> bool {
+        let pending_len: usize = buckets.values().map(|b| b.requests.len()).sum();
+        pending_len as f32 / self.config.max_batch_size as f32 >= self.config.waiting_served_ratio
+    }
+
+    fn bucket_ready(&self, bucket: &Bucket, max_wait: Duration, backed_up: bool) -> bool {
+        bucket.requests.len() >= self.config.max_batch_size
+            || bucket.oldest.elapsed() >= max_wait
+            || (backed_up && !bucket.requests.is_empty())
+    }
+
+    /// Flush every bucket that is currently ready, dispatching each via
+    /// `process_bucket_combined` or `generate_batch` depending on `supports_batching()`,
+    /// and fanning the results back out to their callers
+    pub async fn process_ready_batches<B: ImageBackend + ?Sized>(&self, backend: &B) -> Result<()> {
+        let max_wait = Duration::from_millis(self.config.max_wait_ms);
 
-        let batch: Vec<BatchedRequest> = pending.drain(..).collect();
-        drop(pending); // Release lock early
+        let ready: Vec<Bucket> = {
+            let mut buckets = self.buckets.lock().await;
+            let backed_up = self.is_backed_up(&buckets);
+            let ready_keys: Vec<BucketKey> = buckets
+                .iter()
+                .filter(|(_, bucket)| self.bucket_ready(bucket, max_wait, backed_up))
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            ready_keys
+                .into_iter()
+                .filter_map(|key| buckets.remove(&key))
+                .collect()
+        };
+
+        for bucket in ready {
+            let batch_size = bucket.requests.len();
+            if batch_size == 0 {
+                continue;
+            }
 
-        // Update last batch time
-        *self.last_batch_time.lock().await = Instant::now();
+            debug!(batch_size = batch_size, "Processing batch");
+            self.dequeued
+                .fetch_add(batch_size as u64, Ordering::Relaxed);
 
-        let batch_size = batch.len();
-        debug!(batch_size = batch_size, "Processing batch");
+            if backend.supports_batching() {
+                Self::process_bucket_combined(backend, bucket.requests).await;
+            } else {
+                let (requests, responders, permits) = unzip_batch(bucket.requests);
+                let results = backend.generate_batch(requests).await;
 
-        // For now, process each request individually
-        // A more sophisticated implementation could combine requests
-        // for backends that support true batching
-        for batched in batch {
-            let result = backend.generate(batched.request).await;
-            let _ = batched.response_tx.send(result);
+                for (response_tx, result) in responders.into_iter().zip(results) {
+                    let _ = response_tx.send(result.map_err(|e| ServiceError::Failed(Arc::new(e))));
+                }
+                drop(permits);
+            }
+
+            info!(batch_size = batch_size, "Batch processed");
         }
 
-        info!(batch_size = batch_size, "Batch processed");
         Ok(())
     }
 
-    /// Get the number of pending requests
-    pub async fn pending_count(&self) -> usize {
-        self.pending_requests.lock().await.len()
-    }
-
-    /// Create a combined request from multiple requests (for backends that support batching)
-    #[allow(dead_code)]
-    fn combine_requests(requests: &[GenerateRequest]) -> GenerateRequest {
-        // Take the first request as the base
-        // Sum up the number of images to generate
-        let total_n: u32 = requests.iter().map(|r| r.n).sum();
-        
-        let mut combined = requests[0].clone();
-        combined.n = total_n;
-        combined
-    }
-
-    /// Split a batch response into individual responses
-    #[allow(dead_code)]
-    fn split_response(
-        response: GenerateResponse,
-        original_requests: &[GenerateRequest],
-    ) -> Vec<GenerateResponse> {
-        let mut results = Vec::new();
-        let mut image_index = 0;
-
-        for request in original_requests {
-            let n = request.n as usize;
-            let images: Vec<GeneratedImage> = response
-                .images
-                .iter()
-                .skip(image_index)
-                .take(n)
-                .cloned()
-                .collect();
+    /// Within a ready bucket, sub-group requests by `prompt` (the one dimension
+    /// `BucketKey` doesn't cover, since a single backend call only carries one) and
+    /// dispatch each sub-batch as a single combined `generate` call. A sub-batch of one is
+    /// sent as-is, without going through `combine_requests`/`split_response`. A combined
+    /// call that fails fans the same error out to every caller in that sub-batch, so one
+    /// bad sub-batch never blocks requests in a different sub-batch of the same bucket.
+    async fn process_bucket_combined<B: ImageBackend + ?Sized>(
+        backend: &B,
+        requests: Vec<BatchedRequest>,
+    ) {
+        let mut sub_batches: HashMap<String, Vec<BatchedRequest>> = HashMap::new();
+        for batched in requests {
+            sub_batches
+                .entry(batched.request.prompt.clone())
+                .or_default()
+                .push(batched);
+        }
 
-            results.push(GenerateResponse {
-                images,
-                model: response.model.clone(),
-            });
+        for sub_batch in sub_batches.into_values() {
+            if sub_batch.len() == 1 {
+                let BatchedRequest {
+                    request,
+                    response_tx,
+                    permit,
+                    ..
+                } = sub_batch.into_iter().next().unwrap();
+                let result = backend.generate(request).await;
+                drop(permit);
+                let _ = response_tx.send(result.map_err(|e| ServiceError::Failed(Arc::new(e))));
+                continue;
+            }
 
-            image_index += n;
+            let (requests, responders, permits) = unzip_batch(sub_batch);
+            let combined = combine_requests(&requests);
+
+            match backend.generate(combined).await {
+                Ok(response) => {
+                    for (response_tx, split) in responders
+                        .into_iter()
+                        .zip(split_response(response, &requests))
+                    {
+                        let _ = response_tx.send(Ok(split));
+                    }
+                }
+                Err(e) => {
+                    let shared = Arc::new(e);
+                    for response_tx in responders {
+                        let _ = response_tx.send(Err(ServiceError::Failed(shared.clone())));
+                    }
+                }
+            }
+            drop(permits);
         }
+    }
 
-        results
+    /// Get the total number of requests pending across all buckets
+    pub async fn pending_count(&self) -> usize {
+        self.buckets
+            .lock()
+            .await
+            .values()
+            .map(|b| b.requests.len())
+            .sum()
+    }
+
+    /// Snapshot this batcher's queue depth and lifetime enqueue/dequeue/drop counters,
+    /// for the `/metrics` endpoint to report per-backend queue pressure
+    pub async fn stats(&self) -> BatcherStats {
+        BatcherStats {
+            depth: self.pending_count().await,
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            dequeued: self.dequeued.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drain every bucket and fan `error` out to each pending caller, then record it so
+    /// every `add_request` from now on fails with the same diagnostic instead of a
+    /// channel that silently never resolves. Called once the flush loop itself has died.
+    pub async fn fail_all(&self, error: ServiceError) {
+        let buckets = std::mem::take(&mut *self.buckets.lock().await);
+        for (_, bucket) in buckets {
+            for batched in bucket.requests {
+                let _ = batched.response_tx.send(Err(error.clone()));
+                drop(batched.permit);
+            }
+        }
+        *self.terminal_error.lock().await = Some(error);
     }
 }
 
@@ -195,19 +562,66 @@ impl BatchProcessor {
         Self { batcher, backend }
     }
 
-    /// Start the batch processing loop
+    /// Start the batch processing loop, supervised so a panic inside it fails every
+    /// pending and future caller instead of silently stalling
     pub async fn run(&self) {
-        let interval = Duration::from_millis(10); // Check every 10ms
+        let batcher = self.batcher.clone();
+        let backend = self.backend.clone();
+        run_guarded(batcher.clone(), move || {
+            flush_loop(batcher, move || {
+                let backend = backend.clone();
+                async move { Ok(backend) }
+            })
+        })
+        .await;
+    }
+}
 
-        loop {
-            if self.batcher.should_process().await {
-                if let Err(e) = self.batcher.process_batch(self.backend.as_ref()).await {
-                    tracing::error!(error = %e, "Batch processing failed");
+/// Instead of polling on a fixed interval, this waits on the batcher's `Notify` so a
+/// freshly arrived request is picked up immediately, falling back to `max_wait_ms` so
+/// age-based flushing still happens. `resolve_backend` is called before each flush so
+/// callers that re-resolve their backend per flush (e.g. to pick up load-balancer state)
+/// can share this loop with `BatchProcessor`, which flushes a single fixed backend.
+pub async fn flush_loop<F, Fut>(batcher: Arc<Batcher>, resolve_backend: F)
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<Arc<dyn ImageBackend>>>,
+{
+    loop {
+        batcher.wait_for_work().await;
+
+        if batcher.should_process().await {
+            match resolve_backend().await {
+                Ok(backend) => {
+                    if let Err(e) = batcher.process_ready_batches(backend.as_ref()).await {
+                        tracing::error!(error = %e, "Batch processing failed");
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to resolve backend for batch flush");
                 }
             }
-
-            tokio::time::sleep(interval).await;
         }
     }
 }
 
+/// Run a batch worker loop under supervision: if `worker` panics, the panic is caught via
+/// `tokio::spawn`'s `JoinError` rather than taking down the whole process, and every
+/// request the batcher is holding (plus any that arrive afterward) is failed with a
+/// shared `ServiceError::Failed` instead of hanging forever on a dropped channel. The
+/// loop itself is expected to run forever, so this function only returns once the worker
+/// has died one way or another.
+pub async fn run_guarded<F, Fut>(batcher: Arc<Batcher>, worker: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    if let Err(join_error) = tokio::spawn(worker()).await {
+        tracing::error!(error = %join_error, "Batch worker task panicked");
+        let error = ServiceError::Failed(Arc::new(AppError::Internal(format!(
+            "Batch worker panicked: {}",
+            join_error
+        ))));
+        batcher.fail_all(error).await;
+    }
+}
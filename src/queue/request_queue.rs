@@ -1,20 +1,45 @@
 //! Asynchronous request queue for managing image generation requests
 
+use dashmap::DashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{mpsc, oneshot, Semaphore};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
 use tracing::debug;
+use uuid::Uuid;
 
 use crate::backend::traits::{GenerateRequest, GenerateResponse};
 use crate::error::{AppError, Result};
+use crate::gateway::health_check::HealthCheckManager;
 use crate::gateway::load_balancer::LoadBalancer;
+use crate::gateway::module::ModuleChain;
+use crate::gateway::router::Router;
+use crate::queue::batcher::{flush_loop, run_guarded, BatchConfig, Batcher, BatcherStats};
 
-/// Request with its response channel
+/// Identifier for a background generation job
+pub type JobId = Uuid;
+
+/// Lifecycle state of a background job
+#[derive(Debug, Clone)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done(GenerateResponse),
+    Failed(String),
+}
+
+/// A job's state plus when it reached a terminal state, used to age it out of the job
+/// store after `QueueConfig::job_ttl_ms` even if the caller never polls for it
+struct JobEntry {
+    state: JobState,
+    completed_at: Option<Instant>,
+}
+
+/// Request with the background job it was enqueued as
 struct QueuedRequest {
     request: GenerateRequest,
     backend_name: Option<String>,
-    response_tx: oneshot::Sender<Result<GenerateResponse>>,
+    job_id: JobId,
 }
 
 /// Configuration for the request queue
@@ -26,6 +51,11 @@ pub struct QueueConfig {
     pub max_concurrent: usize,
     /// Request timeout in milliseconds
     pub timeout_ms: u64,
+    /// Continuous-batching configuration for coalescing shape-compatible requests
+    pub batch: BatchConfig,
+    /// How long a completed job's result is kept for `job_result` to fetch before it's
+    /// evicted from the job store
+    pub job_ttl_ms: u64,
 }
 
 impl Default for QueueConfig {
@@ -34,6 +64,8 @@ impl Default for QueueConfig {
             max_queue_size: 1000,
             max_concurrent: 10,
             timeout_ms: 120000, // 2 minutes
+            batch: BatchConfig::default(),
+            job_ttl_ms: 300_000, // 5 minutes
         }
     }
 }
@@ -42,145 +74,394 @@ impl Default for QueueConfig {
 pub struct RequestQueue {
     #[allow(dead_code)]
     load_balancer: Arc<LoadBalancer>,
+    #[allow(dead_code)]
+    router: Arc<Router>,
     request_tx: mpsc::Sender<QueuedRequest>,
     config: QueueConfig,
-    pending_count: AtomicU64,
+    pending_count: Arc<AtomicU64>,
     processed_count: AtomicU64,
+    jobs: Arc<DashMap<JobId, JobEntry>>,
+    batchers: BackendBatchers,
 }
 
+/// Per-backend continuous-batching state: one `Batcher` per backend name, each flushed
+/// by its own background task
+type BackendBatchers = Arc<DashMap<String, Arc<Batcher>>>;
+
 impl RequestQueue {
-    /// Create a new request queue with default configuration
-    pub fn new(load_balancer: Arc<LoadBalancer>) -> Self {
-        Self::with_config(load_balancer, QueueConfig::default())
+    /// Create a new request queue with default configuration, no gateway modules, and no
+    /// passive health reporting
+    pub fn new(load_balancer: Arc<LoadBalancer>, router: Arc<Router>) -> Self {
+        Self::with_config(load_balancer, router, QueueConfig::default())
     }
 
-    /// Create a new request queue with custom configuration
-    pub fn with_config(load_balancer: Arc<LoadBalancer>, config: QueueConfig) -> Self {
+    /// Create a new request queue with custom configuration and no gateway modules
+    pub fn with_config(
+        load_balancer: Arc<LoadBalancer>,
+        router: Arc<Router>,
+        config: QueueConfig,
+    ) -> Self {
+        Self::with_modules(
+            load_balancer,
+            router,
+            config,
+            Arc::new(ModuleChain::default()),
+            None,
+        )
+    }
+
+    /// Create a new request queue that runs every queued request through `module_chain`
+    /// before dispatch and every response through it before the caller sees it. When
+    /// `health_manager` is set, every backend invocation's outcome is reported to it via
+    /// `record_success`/`record_failure`, feeding passive outlier ejection alongside the
+    /// active health prober. Backend selection is delegated to `router`, which
+    /// additionally consults `request.model` when no explicit `backend_name` was given.
+    pub fn with_modules(
+        load_balancer: Arc<LoadBalancer>,
+        router: Arc<Router>,
+        config: QueueConfig,
+        module_chain: Arc<ModuleChain>,
+        health_manager: Option<Arc<HealthCheckManager>>,
+    ) -> Self {
         let (request_tx, request_rx) = mpsc::channel(config.max_queue_size);
         let semaphore = Arc::new(Semaphore::new(config.max_concurrent));
         let lb = load_balancer.clone();
+        let rt = router.clone();
         let timeout_ms = config.timeout_ms;
+        let batch_config = config.batch.clone();
+        let batchers: BackendBatchers = Arc::new(DashMap::new());
+        let jobs: Arc<DashMap<JobId, JobEntry>> = Arc::new(DashMap::new());
+        let pending_count = Arc::new(AtomicU64::new(0));
 
         // Start the worker task
-        tokio::spawn(async move {
-            Self::process_requests(request_rx, lb, semaphore, timeout_ms).await;
-        });
+        {
+            let jobs = jobs.clone();
+            let pending_count = pending_count.clone();
+            let batchers = batchers.clone();
+            tokio::spawn(async move {
+                Self::process_requests(
+                    request_rx,
+                    lb,
+                    rt,
+                    semaphore,
+                    timeout_ms,
+                    module_chain,
+                    batchers,
+                    batch_config,
+                    jobs,
+                    pending_count,
+                    health_manager,
+                )
+                .await;
+            });
+        }
+
+        // Periodically evict completed jobs nobody fetched within `job_ttl_ms`
+        {
+            let jobs = jobs.clone();
+            let ttl = Duration::from_millis(config.job_ttl_ms);
+            tokio::spawn(async move {
+                let sweep_interval = Duration::from_secs(1);
+                loop {
+                    tokio::time::sleep(sweep_interval).await;
+                    jobs.retain(|_, entry| {
+                        entry
+                            .completed_at
+                            .map(|at| at.elapsed() < ttl)
+                            .unwrap_or(true)
+                    });
+                }
+            });
+        }
 
         Self {
             load_balancer,
+            router,
             request_tx,
             config,
-            pending_count: AtomicU64::new(0),
+            pending_count,
             processed_count: AtomicU64::new(0),
+            jobs,
+            batchers,
         }
     }
 
-    /// Submit a request to the queue
+    /// Submit a request and wait for its result, polling the job store internally. This
+    /// is a thin synchronous wrapper around `submit_background`/`job_result` for callers
+    /// that don't need to survive a disconnect.
     pub async fn submit(
         &self,
         request: GenerateRequest,
         backend_name: Option<&str>,
     ) -> Result<GenerateResponse> {
-        // Check if queue is full
+        let job_id = self.submit_background(request, backend_name).await?;
+        let deadline = Duration::from_millis(self.config.timeout_ms);
+        let poll_interval = Duration::from_millis(20);
+        let started = Instant::now();
+
+        loop {
+            if let Some(result) = self.job_result(job_id) {
+                return result;
+            }
+
+            if started.elapsed() >= deadline {
+                self.jobs.remove(&job_id);
+                return Err(AppError::Timeout("Request timed out".to_string()));
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Enqueue a request and return its `JobId` immediately without waiting for
+    /// generation to finish. Poll progress with `job_status` and fetch the outcome with
+    /// `job_result` once it's done.
+    pub async fn submit_background(
+        &self,
+        request: GenerateRequest,
+        backend_name: Option<&str>,
+    ) -> Result<JobId> {
         let pending = self.pending_count.load(Ordering::Relaxed);
         if pending >= self.config.max_queue_size as u64 {
             return Err(AppError::Internal("Request queue is full".to_string()));
         }
 
-        // Create response channel
-        let (response_tx, response_rx) = oneshot::channel();
+        let job_id = Uuid::new_v4();
+        self.jobs.insert(
+            job_id,
+            JobEntry {
+                state: JobState::Queued,
+                completed_at: None,
+            },
+        );
 
         let queued_request = QueuedRequest {
             request,
             backend_name: backend_name.map(String::from),
-            response_tx,
+            job_id,
         };
 
-        // Increment pending count
         self.pending_count.fetch_add(1, Ordering::Relaxed);
 
-        // Send to queue
-        self.request_tx
-            .send(queued_request)
-            .await
-            .map_err(|_| AppError::Internal("Failed to queue request".to_string()))?;
+        if self.request_tx.send(queued_request).await.is_err() {
+            self.jobs.remove(&job_id);
+            self.pending_count.fetch_sub(1, Ordering::Relaxed);
+            return Err(AppError::Internal("Failed to queue request".to_string()));
+        }
 
-        debug!(pending = pending + 1, "Request queued");
+        debug!(pending = pending + 1, job_id = %job_id, "Job queued");
+        Ok(job_id)
+    }
 
-        // Wait for response with timeout
-        let timeout = Duration::from_millis(self.config.timeout_ms);
-        match tokio::time::timeout(timeout, response_rx).await {
-            Ok(Ok(result)) => {
-                self.pending_count.fetch_sub(1, Ordering::Relaxed);
-                result
-            }
-            Ok(Err(_)) => {
-                self.pending_count.fetch_sub(1, Ordering::Relaxed);
-                Err(AppError::Internal("Request processing was cancelled".to_string()))
-            }
-            Err(_) => {
-                self.pending_count.fetch_sub(1, Ordering::Relaxed);
-                Err(AppError::Timeout("Request timed out".to_string()))
-            }
+    /// Current state of a background job, without consuming it
+    pub fn job_status(&self, job_id: JobId) -> Option<JobState> {
+        self.jobs.get(&job_id).map(|entry| entry.state.clone())
+    }
+
+    /// Fetch and remove a background job's outcome. Returns `None` while the job is
+    /// still `Queued`/`Running` (or unknown) so the caller knows to keep polling.
+    pub fn job_result(&self, job_id: JobId) -> Option<Result<GenerateResponse>> {
+        match &self.jobs.get(&job_id)?.state {
+            JobState::Done(_) | JobState::Failed(_) => {}
+            JobState::Queued | JobState::Running => return None,
+        }
+
+        let (_, entry) = self.jobs.remove(&job_id)?;
+        match entry.state {
+            JobState::Done(response) => Some(Ok(response)),
+            JobState::Failed(message) => Some(Err(AppError::BackendError(message))),
+            JobState::Queued | JobState::Running => None,
         }
     }
 
     /// Process requests from the queue
+    #[allow(clippy::too_many_arguments)]
     async fn process_requests(
         mut request_rx: mpsc::Receiver<QueuedRequest>,
         load_balancer: Arc<LoadBalancer>,
+        router: Arc<Router>,
         semaphore: Arc<Semaphore>,
         timeout_ms: u64,
+        module_chain: Arc<ModuleChain>,
+        batchers: BackendBatchers,
+        batch_config: BatchConfig,
+        jobs: Arc<DashMap<JobId, JobEntry>>,
+        pending_count: Arc<AtomicU64>,
+        health_manager: Option<Arc<HealthCheckManager>>,
     ) {
-        while let Some(queued) = request_rx.recv().await {
+        while let Some(mut queued) = request_rx.recv().await {
             let lb = load_balancer.clone();
+            let rt = router.clone();
             let sem = semaphore.clone();
             let timeout = Duration::from_millis(timeout_ms);
+            let modules = module_chain.clone();
+            let batchers = batchers.clone();
+            let batch_config = batch_config.clone();
+            let jobs = jobs.clone();
+            let pending_count = pending_count.clone();
+            let health_manager = health_manager.clone();
 
             tokio::spawn(async move {
+                let job_id = queued.job_id;
+
                 // Acquire semaphore permit
                 let _permit = match sem.acquire().await {
                     Ok(permit) => permit,
                     Err(_) => {
-                        let _ = queued.response_tx.send(Err(AppError::Internal(
-                            "Failed to acquire processing permit".to_string(),
-                        )));
+                        Self::finish_job(
+                            &jobs,
+                            job_id,
+                            Err(AppError::Internal(
+                                "Failed to acquire processing permit".to_string(),
+                            )),
+                        );
+                        pending_count.fetch_sub(1, Ordering::Relaxed);
                         return;
                     }
                 };
 
-                // Select backend
-                let backend = match lb
-                    .select_backend(queued.backend_name.as_deref())
+                if let Some(mut entry) = jobs.get_mut(&job_id) {
+                    entry.state = JobState::Running;
+                }
+
+                // Run the request through the gateway module chain (blocklist, param
+                // clamping, etc.) before it's sent to any backend
+                if let Err(e) = modules.run_request_filters(&mut queued.request) {
+                    Self::finish_job(&jobs, job_id, Err(e));
+                    pending_count.fetch_sub(1, Ordering::Relaxed);
+                    return;
+                }
+
+                // Select backend, consulting the declarative model routing table (and the
+                // model-name heuristic fallback) when no explicit backend was requested
+                let backend = match rt
+                    .route(
+                        queued.backend_name.as_deref(),
+                        queued.request.model.as_deref(),
+                    )
                     .await
                 {
                     Ok(b) => b,
                     Err(e) => {
-                        let _ = queued.response_tx.send(Err(e));
+                        Self::finish_job(&jobs, job_id, Err(e));
+                        pending_count.fetch_sub(1, Ordering::Relaxed);
                         return;
                     }
                 };
 
-                debug!(backend = %backend.name(), "Processing request");
+                debug!(backend = %backend.name(), job_id = %job_id, "Processing request");
+
+                // Give modules a chance to observe (and potentially veto) the selected
+                // backend before dispatch
+                if let Err(e) = modules.run_select_filters(&lb.backend_status(&backend)) {
+                    Self::finish_job(&jobs, job_id, Err(e));
+                    pending_count.fetch_sub(1, Ordering::Relaxed);
+                    return;
+                }
 
-                // Generate images with timeout
-                let result = tokio::time::timeout(timeout, backend.generate(queued.request)).await;
+                // Track this dispatch for least-connections/power-of-two-choices
+                // selection; released automatically however the request ends up finishing
+                let _connection_guard = lb.track_dispatch(backend.name());
 
-                let response = match result {
-                    Ok(Ok(resp)) => Ok(resp),
-                    Ok(Err(e)) => Err(e),
+                // Hand the request to that backend's continuous-batching bucket and wait
+                // for the batch it ends up in to be flushed
+                let batcher = Self::batcher_for(backend.name(), &batchers, &batch_config, &lb);
+                let batch_rx = match batcher.add_request(queued.request).await {
+                    Ok(rx) => rx,
+                    Err(e) => {
+                        Self::finish_job(&jobs, job_id, Err(e));
+                        pending_count.fetch_sub(1, Ordering::Relaxed);
+                        return;
+                    }
+                };
+
+                let result = tokio::time::timeout(timeout, batch_rx).await;
+
+                let mut response = match result {
+                    Ok(Ok(Ok(resp))) => Ok(resp),
+                    Ok(Ok(Err(service_error))) => Err(service_error.into()),
+                    Ok(Err(_)) => Err(AppError::Internal(
+                        "Batch worker dropped the request before completion".to_string(),
+                    )),
                     Err(_) => Err(AppError::Timeout(format!(
                         "Request to {} timed out",
                         backend.name()
                     ))),
                 };
 
-                // Send response
-                let _ = queued.response_tx.send(response);
+                // Feed this outcome back to the health manager so a backend failing live
+                // requests is ejected within one request instead of up to one probe
+                // interval, fusing passive and active health signals
+                if let Some(health_manager) = &health_manager {
+                    match &response {
+                        Ok(_) => health_manager.record_success(backend.name()),
+                        Err(e) => health_manager.record_failure(backend.name(), e),
+                    }
+                }
+
+                if let Ok(resp) = &mut response {
+                    if let Err(e) = modules.run_response_filters(resp) {
+                        response = Err(e);
+                    }
+                }
+
+                Self::finish_job(&jobs, job_id, response);
+                pending_count.fetch_sub(1, Ordering::Relaxed);
             });
         }
     }
 
+    /// Record a job's terminal outcome in the job store
+    fn finish_job(
+        jobs: &Arc<DashMap<JobId, JobEntry>>,
+        job_id: JobId,
+        result: Result<GenerateResponse>,
+    ) {
+        let state = match result {
+            Ok(response) => JobState::Done(response),
+            Err(e) => JobState::Failed(e.to_string()),
+        };
+        jobs.insert(
+            job_id,
+            JobEntry {
+                state,
+                completed_at: Some(Instant::now()),
+            },
+        );
+    }
+
+    /// Fetch this backend's batcher, lazily creating it (and the background task that
+    /// flushes its ready buckets) the first time the backend is seen
+    fn batcher_for(
+        backend_name: &str,
+        batchers: &BackendBatchers,
+        batch_config: &BatchConfig,
+        load_balancer: &Arc<LoadBalancer>,
+    ) -> Arc<Batcher> {
+        if let Some(batcher) = batchers.get(backend_name) {
+            return batcher.clone();
+        }
+
+        let batcher = Arc::new(Batcher::with_config(batch_config.clone()));
+        batchers.insert(backend_name.to_string(), batcher.clone());
+
+        let flush_batcher = batcher.clone();
+        let lb = load_balancer.clone();
+        let name = backend_name.to_string();
+        tokio::spawn(async move {
+            run_guarded(flush_batcher.clone(), move || {
+                flush_loop(flush_batcher, move || {
+                    let lb = lb.clone();
+                    let name = name.clone();
+                    async move { lb.select_backend(Some(&name)).await }
+                })
+            })
+            .await;
+        });
+
+        batcher
+    }
+
     /// Get the number of pending requests
     pub fn pending_count(&self) -> u64 {
         self.pending_count.load(Ordering::Relaxed)
@@ -191,6 +472,16 @@ impl RequestQueue {
         self.processed_count.load(Ordering::Relaxed)
     }
 
+    /// Per-backend batch queue depth and enqueue/dequeue/drop counters, for `/metrics`
+    /// to report which backends are backing up or shedding load
+    pub async fn batcher_stats(&self) -> Vec<(String, BatcherStats)> {
+        let mut stats = Vec::with_capacity(self.batchers.len());
+        for entry in self.batchers.iter() {
+            stats.push((entry.key().clone(), entry.value().stats().await));
+        }
+        stats
+    }
+
     /// Get queue statistics
     pub fn stats(&self) -> QueueStats {
         QueueStats {
@@ -210,4 +501,3 @@ pub struct QueueStats {
     pub max_queue_size: usize,
     pub max_concurrent: usize,
 }
-
@@ -1,7 +1,7 @@
 //! Base64 encoding and decoding utilities
 
-use base64::{engine::general_purpose::STANDARD, Engine};
 use crate::error::{AppError, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
 
 /// Encode binary data to base64 string
 pub fn encode(data: &[u8]) -> String {
@@ -29,7 +29,7 @@ pub fn is_valid(data: &str) -> bool {
     } else {
         data
     };
-    
+
     STANDARD.decode(data.trim()).is_ok()
 }
 
@@ -81,4 +81,3 @@ mod tests {
         assert_eq!(get_format_from_data_url("not a data url"), None);
     }
 }
-
@@ -1,11 +1,13 @@
 //! Response handling module - Base64, file storage, and URL generation
 
 pub mod base64;
-pub mod file;
 pub mod url;
 
+use std::sync::Arc;
+
 use crate::backend::traits::GeneratedImage;
 use crate::error::Result;
+use crate::storage::backend::StorageBackend;
 
 /// Response format options
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,15 +33,15 @@ impl ResponseFormat {
 
 /// Response handler for processing generated images
 pub struct ResponseHandler {
-    file_handler: file::FileHandler,
+    storage_backend: Arc<dyn StorageBackend>,
     url_handler: url::UrlHandler,
 }
 
 impl ResponseHandler {
-    /// Create a new response handler
-    pub fn new(storage_path: String, url_prefix: String) -> Self {
+    /// Create a new response handler around the gateway's shared storage backend
+    pub fn new(storage_backend: Arc<dyn StorageBackend>, url_prefix: String) -> Self {
         Self {
-            file_handler: file::FileHandler::new(storage_path),
+            storage_backend,
             url_handler: url::UrlHandler::new(url_prefix),
         }
     }
@@ -58,9 +60,9 @@ impl ResponseHandler {
             ResponseFormat::Url => {
                 // If we have base64 data, save to file and return URL
                 if let Some(b64_data) = &image.b64_json {
-                    let file_path = self.file_handler.save_base64(b64_data).await?;
+                    let file_path = self.storage_backend.save_base64(b64_data).await?;
                     let url = self.url_handler.generate_url(&file_path);
-                    
+
                     Ok(GeneratedImage {
                         b64_json: None,
                         url: Some(url),
@@ -75,8 +77,8 @@ impl ResponseHandler {
             ResponseFormat::File => {
                 // Save to file and return file path
                 if let Some(b64_data) = &image.b64_json {
-                    let file_path = self.file_handler.save_base64(b64_data).await?;
-                    
+                    let file_path = self.storage_backend.save_base64(b64_data).await?;
+
                     Ok(GeneratedImage {
                         b64_json: None,
                         url: Some(file_path),
@@ -97,12 +99,11 @@ impl ResponseHandler {
         format: ResponseFormat,
     ) -> Result<Vec<GeneratedImage>> {
         let mut results = Vec::with_capacity(images.len());
-        
+
         for image in images {
             results.push(self.process(image, format).await?);
         }
-        
+
         Ok(results)
     }
 }
-
@@ -0,0 +1,21 @@
+//! URL generation for stored images
+
+/// Builds public URLs for images persisted by a `StorageBackend`
+pub struct UrlHandler {
+    url_prefix: String,
+}
+
+impl UrlHandler {
+    /// Create a new URL handler rooted at `url_prefix` (trailing slashes are trimmed)
+    pub fn new(url_prefix: String) -> Self {
+        Self {
+            url_prefix: url_prefix.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Generate the public URL for a blob stored at `rel_path` (the opaque storage key
+    /// returned by `StorageBackend`, e.g. `ab/cd/<digest>.png`)
+    pub fn generate_url(&self, rel_path: &str) -> String {
+        format!("{}/{}", self.url_prefix, rel_path.trim_start_matches('/'))
+    }
+}
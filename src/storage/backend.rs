@@ -0,0 +1,1005 @@
+//! Pluggable storage backend for generated-image blobs
+//!
+//! `StorageBackend` abstracts over where blobs physically live so the gateway can run
+//! statelessly behind many nodes instead of pinning generated images to whichever node
+//! happened to handle the request. Two implementations are provided: [`FilesystemBackend`],
+//! which is the original local-disk layout (content-addressed, sharded into two levels of
+//! two-character subdirectories, with a `.meta.json` ref-count/last-access sidecar per
+//! blob), and [`ObjectStorageBackend`], which stores the same content-addressed blobs in an
+//! S3-compatible bucket. Both return opaque storage keys rather than filesystem paths, so
+//! callers (and signed URLs) never depend on local layout.
+
+use async_trait::async_trait;
+use image::GenericImageView;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::sync::Notify;
+use tracing::debug;
+
+use crate::config::{ObjectStorageConfig, StorageConfig};
+use crate::error::{AppError, Result};
+use crate::response::base64;
+
+/// Relays the progress of an in-flight write so a concurrent `read` of the same key
+/// doesn't observe a partially-written file. Writes in this backend are flushed from a
+/// single in-memory buffer rather than streamed in chunks, so `bytes_written` jumps
+/// straight from `0` to the full length instead of advancing incrementally; it's kept as
+/// a running count (rather than a plain bool) so a future chunked writer can report real
+/// progress without changing this type's shape.
+struct WriteStatus {
+    notify: Notify,
+    bytes_written: AtomicU64,
+    outcome: Mutex<WriteOutcome>,
+}
+
+#[derive(Clone)]
+enum WriteOutcome {
+    InProgress,
+    Done(Arc<Vec<u8>>),
+    Errored(Arc<String>),
+}
+
+impl WriteStatus {
+    fn new() -> Self {
+        Self {
+            notify: Notify::new(),
+            bytes_written: AtomicU64::new(0),
+            outcome: Mutex::new(WriteOutcome::InProgress),
+        }
+    }
+
+    fn complete(&self, data: Vec<u8>) {
+        self.bytes_written
+            .store(data.len() as u64, Ordering::Relaxed);
+        *self.outcome.lock() = WriteOutcome::Done(Arc::new(data));
+        self.notify.notify_waiters();
+    }
+
+    fn fail(&self, message: String) {
+        *self.outcome.lock() = WriteOutcome::Errored(Arc::new(message));
+        self.notify.notify_waiters();
+    }
+
+    /// Wait for the write to finish, returning the bytes it flushed or its error
+    async fn join(&self) -> Result<Vec<u8>> {
+        loop {
+            let notified = self.notify.notified();
+            match self.outcome.lock().clone() {
+                WriteOutcome::Done(data) => return Ok((*data).clone()),
+                WriteOutcome::Errored(message) => {
+                    return Err(AppError::Internal(format!(
+                        "concurrent write failed: {}",
+                        message
+                    )))
+                }
+                WriteOutcome::InProgress => notified.await,
+            }
+        }
+    }
+}
+
+/// Reference-count / last-access metadata persisted alongside each blob (as
+/// `<blob>.meta.json`) so a future garbage-collection pass can tell which blobs are
+/// still referenced and which have gone cold. Only tracked by [`FilesystemBackend`];
+/// [`ObjectStorageBackend`] relies on the bucket's own object metadata instead.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BlobMetadata {
+    ref_count: u64,
+    last_access_unix: u64,
+}
+
+/// On-the-fly resize/re-encode parameters for [`StorageBackend::read_transformed`].
+/// Leaving every field `None` is equivalent to a plain [`StorageBackend::read`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadTransform {
+    /// Target width in pixels; the other dimension is scaled to preserve aspect ratio
+    /// when only one of `width`/`height` is set
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Output format extension, e.g. `"webp"`, `"avif"`, `"jpg"`. Defaults to the
+    /// original blob's format when unset.
+    pub format: Option<String>,
+    /// Encoder quality (0-100), honored by formats that support lossy quality tuning
+    /// (currently JPEG); ignored otherwise
+    pub quality: Option<u8>,
+}
+
+impl ReadTransform {
+    /// Whether applying this transform would be a no-op, i.e. it's equivalent to a
+    /// plain [`StorageBackend::read`]
+    pub fn is_noop(&self) -> bool {
+        self.width.is_none() && self.height.is_none() && self.format.is_none()
+    }
+
+    /// Deterministic cache-key suffix for the derived variant, e.g. `.w512.q80`
+    fn key_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if let Some(width) = self.width {
+            suffix.push_str(&format!(".w{}", width));
+        }
+        if let Some(height) = self.height {
+            suffix.push_str(&format!(".h{}", height));
+        }
+        if let Some(quality) = self.quality {
+            suffix.push_str(&format!(".q{}", quality));
+        }
+        suffix
+    }
+}
+
+/// Storage for generated-image blobs, addressed by opaque keys rather than filesystem
+/// paths so callers don't depend on where (or on which node) a blob physically lives
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Save base64-encoded image data as a content-addressed blob, returning its opaque
+    /// storage key
+    async fn save_base64(&self, b64_data: &str) -> Result<String>;
+
+    /// Save raw image data as a content-addressed blob, returning its opaque storage key
+    async fn save_raw(&self, data: &[u8], format: &str) -> Result<String>;
+
+    /// Read a blob, given its storage key
+    async fn read(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Read a blob with `transform` (resize and/or re-encode) applied, caching the
+    /// derived variant under a deterministic key so repeated requests for the same
+    /// parameters are served without re-decoding. Backends that can't transform images
+    /// locally (e.g. object storage) keep the default, which ignores `transform` and
+    /// returns the original bytes.
+    async fn read_transformed(&self, key: &str, transform: &ReadTransform) -> Result<Vec<u8>> {
+        let _ = transform;
+        self.read(key).await
+    }
+
+    /// Delete a blob, given its storage key
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// List all blobs in storage, as storage keys
+    async fn list(&self) -> Result<Vec<String>>;
+
+    /// Clean up blobs last written more than `max_age_secs` ago (file modification time,
+    /// or the object's `last_modified`), returning the number deleted
+    async fn cleanup(&self, max_age_secs: u64) -> Result<usize>;
+
+    /// Evict least-recently-used blobs until total usage is back under a low watermark
+    /// below `max_bytes`, returning the number evicted. `max_bytes == 0` means
+    /// unbounded (a no-op). Backends that don't track per-blob recency/size locally
+    /// (e.g. object storage, which relies on the bucket's own lifecycle rules) keep the
+    /// default no-op.
+    async fn cleanup_to_capacity(&self, max_bytes: u64) -> Result<usize> {
+        let _ = max_bytes;
+        Ok(0)
+    }
+
+    /// Rebuild any in-memory recency/size index this backend needs from persisted
+    /// state, e.g. an LRU order reconstructed from file modification times. Call once
+    /// at startup before serving traffic. Default no-op.
+    async fn warm(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// The local filesystem path for a storage key, if this backend is local-disk
+    /// backed. Backends with no local representation (e.g. object storage) return
+    /// `None`; callers that need this (e.g. to stream a file directly) must handle it.
+    fn get_path(&self, key: &str) -> Option<PathBuf>;
+
+    /// Deduplication stats, for backends that track them. Only [`FilesystemBackend`]
+    /// does today; other backends keep the default of `None`.
+    fn dedup_stats(&self) -> Option<DedupStats> {
+        None
+    }
+}
+
+/// Fraction of `max_bytes` that [`FilesystemBackend::cleanup_to_capacity`] evicts down
+/// to, so a cleanup pass doesn't immediately re-trigger on the next write
+const LOW_WATERMARK_RATIO: f64 = 0.9;
+
+/// Snapshot of a backend's content-addressed deduplication, reported via `/metrics`
+#[derive(Debug, Clone, Copy)]
+pub struct DedupStats {
+    /// Number of distinct blobs currently on disk
+    pub unique_objects: u64,
+    /// Bytes not re-written because an identical blob already existed
+    pub bytes_saved: u64,
+}
+
+/// Local-filesystem storage backend. Images are stored content-addressed: the storage
+/// key is derived from a digest of the decoded bytes rather than a random name, so
+/// identical outputs land on the same file instead of being written again, and the
+/// digest doubles as a stable, cacheable public URL. Keys are sharded into two levels of
+/// two-character subdirectories (mirroring `storage::cache::ImageCache`'s digest scheme)
+/// to keep any one directory from accumulating every blob the gateway has ever produced.
+///
+/// Dedup is tracked two ways: an on-disk `.meta.json` sidecar per blob (durable, but only
+/// touched opportunistically) and an in-memory `refcounts` map that `delete` consults so
+/// a blob is only unlinked once its last referencing caller drops it. The in-memory map
+/// starts empty on every restart, so a blob saved in a prior process generation is always
+/// treated as having exactly one known reference until this process saves or deletes it
+/// again; this only risks an early unlink of a blob that's still logically referenced by
+/// state outside this process's memory (e.g. a URL handed out before a restart), not a
+/// leak.
+pub struct FilesystemBackend {
+    storage_path: PathBuf,
+    refcounts: Mutex<HashMap<String, u64>>,
+    unique_objects: AtomicU64,
+    bytes_saved: AtomicU64,
+    /// Keys currently being written, so a concurrent `read` can attach to the write in
+    /// progress instead of observing a truncated file
+    writes: Mutex<HashMap<String, Arc<WriteStatus>>>,
+    /// Keys ordered by last access, each mapped to its size in bytes, so
+    /// `cleanup_to_capacity` knows what to evict first. Capacity is effectively
+    /// unbounded as an LRU (eviction is driven by `total_bytes` vs. a caller-supplied
+    /// ceiling, not by entry count).
+    lru: Mutex<LruCache<String, u64>>,
+    /// Cumulative size of all blobs currently on disk, kept in sync with `lru`
+    total_bytes: AtomicU64,
+    /// When set, newly-saved blobs are eagerly transcoded to this format and the
+    /// derived variant cached alongside the original, so a later on-the-fly read
+    /// request for that format is already served from disk instead of transcoding
+    /// on the caller's critical path
+    preferred_save_format: Option<String>,
+}
+
+impl FilesystemBackend {
+    /// Create a new filesystem-backed storage backend rooted at `StorageConfig.base_path`
+    pub fn new(config: &StorageConfig) -> Self {
+        Self {
+            storage_path: PathBuf::from(config.base_path.clone()),
+            refcounts: Mutex::new(HashMap::new()),
+            unique_objects: AtomicU64::new(0),
+            bytes_saved: AtomicU64::new(0),
+            writes: Mutex::new(HashMap::new()),
+            lru: Mutex::new(LruCache::unbounded()),
+            total_bytes: AtomicU64::new(0),
+            preferred_save_format: config.preferred_save_format.clone(),
+        }
+    }
+
+    /// Remove a blob's bookkeeping (refcount, LRU entry, `total_bytes`, unique-object
+    /// count) after its file has actually been unlinked from disk
+    fn forget(&self, key: &str) {
+        self.refcounts.lock().remove(key);
+        self.unique_objects.fetch_sub(1, Ordering::Relaxed);
+        if let Some(size) = self.lru.lock().pop(key) {
+            self.total_bytes.fetch_sub(size, Ordering::Relaxed);
+        }
+    }
+
+    /// Remove `key`'s `writes` slot, but only if it's still the one this caller
+    /// registered: a concurrent `read` may have cloned the `Arc` before this call
+    /// claimed the slot, and a later save of the same key may have registered its own
+    /// status after a previous owner already removed theirs.
+    fn clear_write_slot(&self, key: &str, status: &Arc<WriteStatus>) {
+        let mut writes = self.writes.lock();
+        if matches!(writes.get(key), Some(current) if Arc::ptr_eq(current, status)) {
+            writes.remove(key);
+        }
+    }
+
+    /// Ensure the storage directory exists
+    async fn ensure_storage_dir(&self) -> Result<()> {
+        if !self.storage_path.exists() {
+            fs::create_dir_all(&self.storage_path)
+                .await
+                .map_err(AppError::Io)?;
+            debug!(path = ?self.storage_path, "Created storage directory");
+        }
+        Ok(())
+    }
+
+    /// Hash `data`, derive its sharded storage key from the digest, and write it there
+    /// unless a blob with that digest already exists. Either way, the blob's metadata
+    /// sidecar is touched so reference count and last-access time stay current.
+    ///
+    /// The on-disk existence check and the decision it gates (new blob vs. dedup hit)
+    /// are made under the same `writes` slot a concurrent `read` of this key would
+    /// attach to, not just the `fs::write` call itself: claiming the slot (inserting a
+    /// fresh `WriteStatus` only if none is already there) is the atomic step, so at most
+    /// one concurrent `save_content_addressed` call for a given digest ever takes the
+    /// new-blob branch. Without this, two callers could both observe `fs::metadata` as
+    /// missing and both increment `unique_objects`/`total_bytes`, corrupting `dedup_stats`
+    /// and the byte-budget math `cleanup_to_capacity` relies on.
+    async fn save_content_addressed(&self, data: &[u8], format: &str) -> Result<String> {
+        self.ensure_storage_dir().await?;
+
+        let digest = content_digest(data);
+        let key = shard_path(&digest, format);
+        let file_path = self.storage_path.join(&key);
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await.map_err(AppError::Io)?;
+        }
+
+        let (status, is_winner) = {
+            let mut writes = self.writes.lock();
+            match writes.get(&key) {
+                Some(existing) => (existing.clone(), false),
+                None => {
+                    let status = Arc::new(WriteStatus::new());
+                    writes.insert(key.clone(), status.clone());
+                    (status, true)
+                }
+            }
+        };
+
+        if is_winner {
+            // Holding this key's slot excludes every other concurrent caller from
+            // making the new-blob-vs-dedup decision at the same time, so the
+            // existence check below is safe to treat as authoritative.
+            if fs::metadata(&file_path).await.is_ok() {
+                debug!(path = ?file_path, "Blob already exists, skipping write");
+                self.bytes_saved
+                    .fetch_add(data.len() as u64, Ordering::Relaxed);
+                self.lru.lock().get(&key);
+                status.complete(data.to_vec());
+                self.clear_write_slot(&key, &status);
+            } else {
+                let write_result = fs::write(&file_path, data).await.map_err(AppError::Io);
+                match &write_result {
+                    Ok(()) => status.complete(data.to_vec()),
+                    Err(e) => status.fail(e.to_string()),
+                }
+
+                // Clear this key's slot before surfacing a write error, so a caller
+                // that retries isn't left permanently joining a failed status. Only
+                // clear it if it's still ours: a concurrent `read` may have already
+                // observed and cloned this status, but only the owner removes it.
+                self.clear_write_slot(&key, &status);
+                write_result?;
+
+                debug!(path = ?file_path, size = data.len(), "Saved new content-addressed blob");
+                self.unique_objects.fetch_add(1, Ordering::Relaxed);
+                self.total_bytes
+                    .fetch_add(data.len() as u64, Ordering::Relaxed);
+                self.lru.lock().put(key.clone(), data.len() as u64);
+            }
+        } else {
+            // Identical content is already being saved by another in-flight call for
+            // this key; wait for it to land instead of racing its existence check,
+            // and count this call as a dedup hit.
+            status.join().await?;
+            self.bytes_saved
+                .fetch_add(data.len() as u64, Ordering::Relaxed);
+            self.lru.lock().get(&key);
+        }
+
+        self.touch_metadata(&file_path).await?;
+        *self.refcounts.lock().entry(key.clone()).or_insert(0) += 1;
+
+        if let Some(preferred) = self.preferred_save_format.clone() {
+            if preferred != format {
+                let transform = ReadTransform {
+                    format: Some(preferred),
+                    ..Default::default()
+                };
+                if let Err(err) = self.read_transformed(&key, &transform).await {
+                    debug!(error = %err, key = %key, "Eager transcode to preferred save format failed");
+                }
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// Bump a blob's reference count and last-access time in its metadata sidecar,
+    /// creating the sidecar if this is its first reference
+    async fn touch_metadata(&self, blob_path: &Path) -> Result<()> {
+        let meta_path = metadata_path(blob_path);
+
+        let mut meta = match fs::read(&meta_path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(_) => BlobMetadata::default(),
+        };
+        meta.ref_count += 1;
+        meta.last_access_unix = unix_now();
+
+        let bytes = serde_json::to_vec(&meta)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize blob metadata: {}", e)))?;
+        fs::write(&meta_path, bytes).await.map_err(AppError::Io)?;
+
+        Ok(())
+    }
+
+    /// Recursively walk a shard directory, collecting blob keys relative to
+    /// `storage_path` (metadata sidecars are skipped)
+    async fn list_dir(&self, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+        let mut entries = fs::read_dir(dir).await.map_err(AppError::Io)?;
+
+        while let Some(entry) = entries.next_entry().await.map_err(AppError::Io)? {
+            let path = entry.path();
+            let file_type = entry.file_type().await.map_err(AppError::Io)?;
+
+            if file_type.is_dir() {
+                Box::pin(self.list_dir(&path, files)).await?;
+            } else if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                if let Ok(rel) = path.strip_prefix(&self.storage_path) {
+                    files.push(rel.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FilesystemBackend {
+    async fn save_base64(&self, b64_data: &str) -> Result<String> {
+        let image_data = base64::decode(b64_data)?;
+        let format = detect_image_format(&image_data).unwrap_or("png");
+        self.save_content_addressed(&image_data, format).await
+    }
+
+    async fn save_raw(&self, data: &[u8], format: &str) -> Result<String> {
+        self.save_content_addressed(data, format).await
+    }
+
+    async fn read(&self, key: &str) -> Result<Vec<u8>> {
+        // If a write to this key is in flight, attach to it instead of racing it:
+        // reading the file directly here could observe a partially-flushed blob.
+        let in_flight = self.writes.lock().get(key).cloned();
+        if let Some(status) = in_flight {
+            return status.join().await;
+        }
+
+        let file_path = self.storage_path.join(key);
+        let data = fs::read(&file_path).await.map_err(AppError::Io)?;
+        self.lru.lock().get(key);
+        Ok(data)
+    }
+
+    async fn read_transformed(&self, key: &str, transform: &ReadTransform) -> Result<Vec<u8>> {
+        if transform.is_noop() {
+            return self.read(key).await;
+        }
+
+        let derived_key = derived_key(key, transform);
+        let derived_path = self.storage_path.join(&derived_key);
+
+        if fs::metadata(&derived_path).await.is_ok() {
+            self.lru.lock().get(&derived_key);
+            return fs::read(&derived_path).await.map_err(AppError::Io);
+        }
+
+        let original = self.read(key).await?;
+        let derived = transform_image(&original, transform)?;
+
+        if let Some(parent) = derived_path.parent() {
+            fs::create_dir_all(parent).await.map_err(AppError::Io)?;
+        }
+        fs::write(&derived_path, &derived)
+            .await
+            .map_err(AppError::Io)?;
+
+        self.lru.lock().put(derived_key, derived.len() as u64);
+        self.total_bytes
+            .fetch_add(derived.len() as u64, Ordering::Relaxed);
+
+        Ok(derived)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        // Drop this caller's reference; only unlink the blob once the last known
+        // reference is gone (a key with no tracked refcount, e.g. one saved in a
+        // previous process generation, is treated as having exactly one reference).
+        let should_unlink = {
+            let mut refcounts = self.refcounts.lock();
+            match refcounts.get_mut(key) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    false
+                }
+                Some(_) => {
+                    refcounts.remove(key);
+                    true
+                }
+                None => true,
+            }
+        };
+
+        if !should_unlink {
+            return Ok(());
+        }
+
+        let file_path = self.storage_path.join(key);
+        fs::remove_file(&file_path).await.map_err(AppError::Io)?;
+        let _ = fs::remove_file(metadata_path(&file_path)).await;
+        self.forget(key);
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        self.list_dir(&self.storage_path, &mut files).await?;
+        Ok(files)
+    }
+
+    async fn cleanup(&self, max_age_secs: u64) -> Result<usize> {
+        let mut deleted = 0;
+        let now = SystemTime::now();
+        let max_age = std::time::Duration::from_secs(max_age_secs);
+
+        for key in self.list().await? {
+            let file_path = self.storage_path.join(&key);
+            if let Ok(metadata) = fs::metadata(&file_path).await {
+                if let Ok(modified) = metadata.modified() {
+                    if let Ok(age) = now.duration_since(modified) {
+                        if age > max_age {
+                            if fs::remove_file(&file_path).await.is_ok() {
+                                let _ = fs::remove_file(metadata_path(&file_path)).await;
+                                self.forget(&key);
+                                deleted += 1;
+                                debug!(path = ?file_path, "Deleted old file");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    async fn cleanup_to_capacity(&self, max_bytes: u64) -> Result<usize> {
+        if max_bytes == 0 {
+            return Ok(0);
+        }
+        let target = (max_bytes as f64 * LOW_WATERMARK_RATIO) as u64;
+
+        let mut deleted = 0;
+        while self.total_bytes.load(Ordering::Relaxed) > target {
+            let Some((key, size)) = self.lru.lock().pop_lru() else {
+                break;
+            };
+
+            let file_path = self.storage_path.join(&key);
+            if fs::remove_file(&file_path).await.is_ok() {
+                let _ = fs::remove_file(metadata_path(&file_path)).await;
+                self.refcounts.lock().remove(&key);
+                self.unique_objects.fetch_sub(1, Ordering::Relaxed);
+                self.total_bytes.fetch_sub(size, Ordering::Relaxed);
+                deleted += 1;
+                debug!(path = ?file_path, "Evicted least-recently-used blob over capacity");
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    async fn warm(&self) -> Result<()> {
+        let mut entries: Vec<(String, u64, SystemTime)> = Vec::new();
+
+        for key in self.list().await? {
+            let file_path = self.storage_path.join(&key);
+            if let Ok(metadata) = fs::metadata(&file_path).await {
+                let accessed = metadata
+                    .accessed()
+                    .or_else(|_| metadata.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                entries.push((key, metadata.len(), accessed));
+            }
+        }
+
+        // Oldest-accessed first, so inserting in order leaves the LRU with the same
+        // recency ranking the filesystem already recorded
+        entries.sort_by_key(|(_, _, accessed)| *accessed);
+
+        let mut lru = self.lru.lock();
+        let mut total = 0u64;
+        for (key, size, _) in entries {
+            lru.put(key, size);
+            total += size;
+        }
+        self.total_bytes.store(total, Ordering::Relaxed);
+        self.unique_objects
+            .store(lru.len() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn get_path(&self, key: &str) -> Option<PathBuf> {
+        self.lru.lock().get(key);
+        Some(self.storage_path.join(key))
+    }
+
+    fn dedup_stats(&self) -> Option<DedupStats> {
+        Some(DedupStats {
+            unique_objects: self.unique_objects.load(Ordering::Relaxed),
+            bytes_saved: self.bytes_saved.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// S3-compatible object storage backend. Uses the same content-addressing and sharding
+/// scheme as [`FilesystemBackend`] so keys are stable and collide-free, just written
+/// under `prefix/` in `bucket` instead of a local directory. Per-blob ref-counting isn't
+/// tracked (the bucket's own `last_modified` stands in for last-access), so `cleanup`
+/// is a straight age sweep over `last_modified`.
+pub struct ObjectStorageBackend {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl ObjectStorageBackend {
+    /// Build an S3 client from `config`, resolving credentials and region the same way
+    /// the AWS SDK normally does (explicit config, falling back to environment/instance
+    /// profile) unless `endpoint` points the client at a non-AWS S3-compatible store
+    pub async fn new(config: &ObjectStorageConfig) -> Result<Self> {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new(config.region.clone()));
+
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (&config.access_key_id, &config.secret_access_key)
+        {
+            loader = loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                access_key_id,
+                secret_access_key,
+                None,
+                None,
+                "object-storage-config",
+            ));
+        }
+
+        let sdk_config = loader.load().await;
+        let mut s3_builder = aws_sdk_s3::config::Builder::from(&sdk_config);
+        if let Some(endpoint) = &config.endpoint {
+            s3_builder = s3_builder.endpoint_url(endpoint).force_path_style(true);
+        }
+
+        Ok(Self {
+            client: aws_sdk_s3::Client::from_conf(s3_builder.build()),
+            bucket: config.bucket.clone(),
+            prefix: config.prefix.clone(),
+        })
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        if self.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.prefix.trim_end_matches('/'), key)
+        }
+    }
+
+    async fn put_content_addressed(&self, data: &[u8], format: &str) -> Result<String> {
+        let digest = content_digest(data);
+        let key = shard_path(&digest, format);
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(&key))
+            .body(aws_sdk_s3::primitives::ByteStream::from(data.to_vec()))
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 put_object failed: {}", e)))?;
+
+        Ok(key)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ObjectStorageBackend {
+    async fn save_base64(&self, b64_data: &str) -> Result<String> {
+        let image_data = base64::decode(b64_data)?;
+        let format = detect_image_format(&image_data).unwrap_or("png");
+        self.put_content_addressed(&image_data, format).await
+    }
+
+    async fn save_raw(&self, data: &[u8], format: &str) -> Result<String> {
+        self.put_content_addressed(data, format).await
+    }
+
+    async fn read(&self, key: &str) -> Result<Vec<u8>> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 get_object failed: {}", e)))?;
+
+        let bytes = output
+            .body
+            .collect()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 object body read failed: {}", e)))?;
+
+        Ok(bytes.into_bytes().to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 delete_object failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let mut request = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&self.prefix);
+            if let Some(token) = &continuation_token {
+                request = request.continuation_token(token);
+            }
+
+            let output = request
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("S3 list_objects_v2 failed: {}", e)))?;
+
+            for object in output.contents() {
+                if let Some(object_key) = object.key() {
+                    let key = match self.prefix.is_empty() {
+                        true => object_key.to_string(),
+                        false => object_key
+                            .strip_prefix(&format!("{}/", self.prefix.trim_end_matches('/')))
+                            .unwrap_or(object_key)
+                            .to_string(),
+                    };
+                    keys.push(key);
+                }
+            }
+
+            continuation_token = output.next_continuation_token().map(|t| t.to_string());
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(keys)
+    }
+
+    async fn cleanup(&self, max_age_secs: u64) -> Result<usize> {
+        let max_age = std::time::Duration::from_secs(max_age_secs);
+        let now = SystemTime::now();
+        let mut deleted = 0;
+
+        let mut continuation_token = None;
+        loop {
+            let mut request = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&self.prefix);
+            if let Some(token) = &continuation_token {
+                request = request.continuation_token(token);
+            }
+
+            let output = request
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("S3 list_objects_v2 failed: {}", e)))?;
+
+            for object in output.contents() {
+                let Some(object_key) = object.key() else {
+                    continue;
+                };
+                let Some(last_modified) = object.last_modified() else {
+                    continue;
+                };
+                let modified =
+                    UNIX_EPOCH + std::time::Duration::from_secs(last_modified.secs().max(0) as u64);
+
+                if let Ok(age) = now.duration_since(modified) {
+                    if age > max_age {
+                        self.client
+                            .delete_object()
+                            .bucket(&self.bucket)
+                            .key(object_key)
+                            .send()
+                            .await
+                            .map_err(|e| {
+                                AppError::Internal(format!("S3 delete_object failed: {}", e))
+                            })?;
+                        deleted += 1;
+                    }
+                }
+            }
+
+            continuation_token = output.next_continuation_token().map(|t| t.to_string());
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    fn get_path(&self, _key: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Build the configured storage backend from `StorageConfig`: `"filesystem"` (the
+/// default) stores blobs under `base_path`; `"s3"` talks to the bucket described by
+/// `object_storage`
+pub async fn build_storage_backend(
+    config: &crate::config::StorageConfig,
+) -> Result<Arc<dyn StorageBackend>> {
+    match config.backend.as_str() {
+        "s3" | "object_storage" => Ok(Arc::new(
+            ObjectStorageBackend::new(&config.object_storage).await?,
+        )),
+        _ => Ok(Arc::new(FilesystemBackend::new(config))),
+    }
+}
+
+/// Derive a blob's sharded storage key from its digest: the first two and next two
+/// digest characters become nested subdirectories, e.g. `ab/cd/<digest>.png`
+fn shard_path(digest: &str, format: &str) -> String {
+    let shard_a = &digest[0..2];
+    let shard_b = &digest[2..4];
+    format!("{}/{}/{}.{}", shard_a, shard_b, digest, format)
+}
+
+/// Derive the deterministic cache key a transformed variant of `key` is stored under,
+/// e.g. `ab/cd/<digest>.w512.q80.webp`
+fn derived_key(key: &str, transform: &ReadTransform) -> String {
+    let (base, original_ext) = match key.rsplit_once('.') {
+        Some((base, ext)) => (base, ext),
+        None => (key, ""),
+    };
+    let ext = transform.format.as_deref().unwrap_or(original_ext);
+    format!("{}{}.{}", base, transform.key_suffix(), ext)
+}
+
+/// Decode `data`, apply `transform`'s resize and/or re-encode, and return the encoded
+/// bytes of the derived image
+fn transform_image(data: &[u8], transform: &ReadTransform) -> Result<Vec<u8>> {
+    let img = image::load_from_memory(data)
+        .map_err(|e| AppError::Internal(format!("Failed to decode image for transform: {}", e)))?;
+
+    let img = match (transform.width, transform.height) {
+        (None, None) => img,
+        (width, height) => {
+            let target_w = width.unwrap_or(img.width());
+            let target_h = height.unwrap_or(img.height());
+            img.resize(target_w, target_h, image::imageops::FilterType::Lanczos3)
+        }
+    };
+
+    let mut encoded = Vec::new();
+    match transform.format.as_deref() {
+        Some("jpg") | Some("jpeg") => {
+            let quality = transform.quality.unwrap_or(85);
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, quality)
+                .encode_image(&img)
+                .map_err(|e| AppError::Internal(format!("Failed to encode JPEG: {}", e)))?;
+        }
+        Some(other) => {
+            let output_format = image::ImageFormat::from_extension(other).ok_or_else(|| {
+                AppError::Internal(format!("Unsupported transform output format '{}'", other))
+            })?;
+            img.write_to(&mut std::io::Cursor::new(&mut encoded), output_format)
+                .map_err(|e| AppError::Internal(format!("Failed to encode image: {}", e)))?;
+        }
+        None => {
+            let original_format = image::guess_format(data).unwrap_or(image::ImageFormat::Png);
+            img.write_to(&mut std::io::Cursor::new(&mut encoded), original_format)
+                .map_err(|e| AppError::Internal(format!("Failed to encode image: {}", e)))?;
+        }
+    }
+
+    Ok(encoded)
+}
+
+/// The metadata sidecar path for a given blob path
+fn metadata_path(blob_path: &Path) -> PathBuf {
+    let mut name = blob_path.as_os_str().to_owned();
+    name.push(".meta.json");
+    PathBuf::from(name)
+}
+
+/// Hash `data` into a digest that is safe to use as a filename and shard prefix
+fn content_digest(data: &[u8]) -> String {
+    let mut hasher = Sha512::new();
+    hasher.update(data);
+    ::base64::Engine::encode(
+        &::base64::engine::general_purpose::URL_SAFE_NO_PAD,
+        hasher.finalize(),
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Detect image format from binary data using magic bytes
+fn detect_image_format(data: &[u8]) -> Option<&'static str> {
+    if data.len() < 8 {
+        return None;
+    }
+
+    // PNG: 89 50 4E 47 0D 0A 1A 0A
+    if data.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Some("png");
+    }
+
+    // JPEG: FF D8 FF
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some("jpg");
+    }
+
+    // GIF: GIF87a or GIF89a
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some("gif");
+    }
+
+    // WebP: RIFF....WEBP
+    if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+
+    // BMP: BM
+    if data.starts_with(b"BM") {
+        return Some("bmp");
+    }
+
+    // ISO base media container (HEIF/AVIF/HEIC): an `ftyp` box at offset 4 naming the
+    // brand that decides which codec the payload actually uses
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        match &data[8..12] {
+            b"avif" | b"avis" => return Some("avif"),
+            b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" | b"mif1" => {
+                return Some("heic")
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_png() {
+        let png_header = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        assert_eq!(detect_image_format(&png_header), Some("png"));
+    }
+
+    #[test]
+    fn test_detect_jpeg() {
+        let jpeg_header = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46];
+        assert_eq!(detect_image_format(&jpeg_header), Some("jpg"));
+    }
+
+    #[test]
+    fn test_shard_path_nests_on_digest_prefix() {
+        let digest = "abcdEFGH1234";
+        assert_eq!(shard_path(digest, "png"), "ab/cd/abcdEFGH1234.png");
+    }
+
+    #[test]
+    fn test_content_digest_is_stable() {
+        assert_eq!(content_digest(b"hello"), content_digest(b"hello"));
+        assert_ne!(content_digest(b"hello"), content_digest(b"world"));
+    }
+}
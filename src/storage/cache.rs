@@ -0,0 +1,232 @@
+//! Content-addressable disk cache for generated images
+//!
+//! Entries are keyed on a stable hash of the request parameters and stored
+//! under a content integrity digest, mirroring the cacache/ssri model: the
+//! digest is recomputed on every read so a partially-written or corrupted
+//! file is evicted instead of served.
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use lru::LruCache;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha512};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::fs;
+use tracing::{debug, warn};
+
+use crate::backend::traits::GenerateRequest;
+use crate::config::StorageConfig;
+use crate::error::Result;
+
+/// An image retrieved from the cache, with its integrity digest already verified
+#[derive(Debug, Clone)]
+pub struct CachedImage {
+    pub data: Vec<u8>,
+    pub content_type: String,
+    pub integrity: String,
+}
+
+/// Index entry tracking where a logical cache key's bytes live on disk
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    integrity: String,
+    content_type: String,
+    size: u64,
+}
+
+/// Content-addressable cache of generated images, backed by disk, with an
+/// in-memory LRU index bounding total size and entry count
+pub struct ImageCache {
+    cache_dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<LruCache<String, CacheEntry>>,
+    total_bytes: AtomicU64,
+}
+
+impl ImageCache {
+    /// Create a new image cache rooted under `StorageConfig.base_path`
+    pub fn new(config: &StorageConfig) -> Self {
+        let index = if config.max_cache_entries == 0 {
+            LruCache::unbounded()
+        } else {
+            LruCache::new(
+                NonZeroUsize::new(config.max_cache_entries as usize)
+                    .unwrap_or(NonZeroUsize::new(1).unwrap()),
+            )
+        };
+
+        Self {
+            cache_dir: Path::new(&config.base_path).join("cache"),
+            max_bytes: config.max_cache_bytes,
+            index: Mutex::new(index),
+            total_bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Compute a stable cache key from the fields of a `GenerateRequest` that
+    /// affect the resulting image
+    pub fn request_key(request: &GenerateRequest) -> String {
+        let mut hasher = Sha512::new();
+        hasher.update(request.prompt.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(request.negative_prompt.as_deref().unwrap_or("").as_bytes());
+        hasher.update([0u8]);
+        hasher.update(request.n.to_le_bytes());
+        hasher.update(request.width.to_le_bytes());
+        hasher.update(request.height.to_le_bytes());
+        hasher.update(request.model.as_deref().unwrap_or("").as_bytes());
+        hasher.update([0u8]);
+        hasher.update(request.seed.unwrap_or(-1).to_le_bytes());
+        hasher.update(request.guidance_scale.unwrap_or(0.0).to_le_bytes());
+        hasher.update(request.num_inference_steps.unwrap_or(0).to_le_bytes());
+        URL_SAFE_NO_PAD.encode(hasher.finalize())
+    }
+
+    /// Look up a cache entry by key, verifying the content digest on the way out
+    pub async fn cache_get(&self, key: &str) -> Option<CachedImage> {
+        let entry = self.index.lock().get(key).cloned()?;
+        let path = self.entry_path(&entry.integrity);
+
+        match fs::read(&path).await {
+            Ok(data) => {
+                if content_integrity(&data) != entry.integrity {
+                    warn!(key = %key, "Cache entry failed integrity check, evicting");
+                    self.evict(key).await;
+                    return None;
+                }
+                debug!(key = %key, size = data.len(), "Cache hit");
+                Some(CachedImage {
+                    data,
+                    content_type: entry.content_type,
+                    integrity: entry.integrity,
+                })
+            }
+            Err(e) => {
+                debug!(key = %key, error = %e, "Cache file missing on disk, evicting stale index entry");
+                self.evict(key).await;
+                None
+            }
+        }
+    }
+
+    /// Store bytes under `key`, writing the content-addressed backing file if needed
+    pub async fn cache_put(&self, key: String, bytes: Vec<u8>, content_type: String) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir).await?;
+
+        let integrity = content_integrity(&bytes);
+        let path = self.entry_path(&integrity);
+
+        if fs::metadata(&path).await.is_err() {
+            let tmp_path = path.with_extension("tmp");
+            fs::write(&tmp_path, &bytes).await?;
+            fs::rename(&tmp_path, &path).await?;
+        }
+
+        let size = bytes.len() as u64;
+        let evicted = {
+            let mut index = self.index.lock();
+            let previous = index.put(
+                key,
+                CacheEntry {
+                    integrity,
+                    content_type,
+                    size,
+                },
+            );
+            previous
+        };
+
+        if let Some(previous) = evicted {
+            self.total_bytes.fetch_sub(previous.size, Ordering::Relaxed);
+            self.remove_backing_file_if_unreferenced(&previous.integrity)
+                .await;
+        }
+
+        self.total_bytes.fetch_add(size, Ordering::Relaxed);
+        self.enforce_byte_budget().await;
+
+        Ok(())
+    }
+
+    /// Evict least-recently-used entries until the cache is back under `max_bytes`
+    async fn enforce_byte_budget(&self) {
+        if self.max_bytes == 0 {
+            return;
+        }
+
+        loop {
+            if self.total_bytes.load(Ordering::Relaxed) <= self.max_bytes {
+                return;
+            }
+
+            let popped = self.index.lock().pop_lru();
+            match popped {
+                Some((key, entry)) => {
+                    self.total_bytes.fetch_sub(entry.size, Ordering::Relaxed);
+                    debug!(key = %key, "Evicting cache entry over byte budget");
+                    self.remove_backing_file_if_unreferenced(&entry.integrity)
+                        .await;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Remove a single key from the index and its backing file
+    async fn evict(&self, key: &str) {
+        if let Some(entry) = self.index.lock().pop(key) {
+            self.total_bytes.fetch_sub(entry.size, Ordering::Relaxed);
+            self.remove_backing_file_if_unreferenced(&entry.integrity)
+                .await;
+        }
+    }
+
+    /// Delete the file for a digest if no remaining index entry still points at it
+    async fn remove_backing_file_if_unreferenced(&self, integrity: &str) {
+        let still_referenced = self
+            .index
+            .lock()
+            .iter()
+            .any(|(_, entry)| entry.integrity == integrity);
+
+        if !still_referenced {
+            let _ = fs::remove_file(self.entry_path(integrity)).await;
+        }
+    }
+
+    fn entry_path(&self, integrity: &str) -> PathBuf {
+        let digest_hex = integrity.replace(['/', '+', '='], "_");
+        self.cache_dir.join(digest_hex)
+    }
+}
+
+/// Compute a `sha512-<base64>` content integrity digest, matching the ssri convention
+pub fn content_integrity(data: &[u8]) -> String {
+    let mut hasher = Sha512::new();
+    hasher.update(data);
+    format!("sha512-{}", STANDARD.encode(hasher.finalize()))
+}
+
+/// Compute the content integrity digest of a file on disk without buffering it whole,
+/// reading in bounded chunks instead
+pub async fn hash_file(path: &Path) -> Result<String> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha512::new();
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(format!("sha512-{}", STANDARD.encode(hasher.finalize())))
+}
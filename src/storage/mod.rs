@@ -0,0 +1,4 @@
+//! Storage module - content-addressable caching and file persistence for generated images
+
+pub mod backend;
+pub mod cache;
@@ -11,7 +11,7 @@ use generative_img_serving::middleware::auth::AuthLayer;
 async fn create_test_app() -> Router {
     Router::new()
         .route("/test", axum::routing::get(|| async { "OK" }))
-        .layer(AuthLayer::new(vec![
+        .layer(AuthLayer::with_api_keys(vec![
             "valid-key-1".to_string(),
             "valid-key-2".to_string(),
         ]))
@@ -93,7 +93,7 @@ async fn test_auth_health_endpoint_bypass() {
     let app = Router::new()
         .route("/health", axum::routing::get(|| async { "healthy" }))
         .route("/test", axum::routing::get(|| async { "OK" }))
-        .layer(AuthLayer::new(vec!["valid-key".to_string()]));
+        .layer(AuthLayer::with_api_keys(vec!["valid-key".to_string()]));
     
     // Health endpoint should bypass auth
     let response = app
@@ -114,7 +114,7 @@ async fn test_auth_health_endpoint_bypass() {
 async fn test_auth_empty_keys_allows_all() {
     let app = Router::new()
         .route("/test", axum::routing::get(|| async { "OK" }))
-        .layer(AuthLayer::new(vec![]));
+        .layer(AuthLayer::with_api_keys(vec![]));
     
     // When no keys configured, all requests should be allowed
     let response = app
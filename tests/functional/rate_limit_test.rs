@@ -2,7 +2,7 @@
 
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    http::{header::AUTHORIZATION, Request, StatusCode},
     Router,
 };
 use tower::ServiceExt;
@@ -120,6 +120,59 @@ async fn test_rate_limit_exceeded() {
     assert!(rate_limited, "Expected rate limiting to kick in");
 }
 
+#[tokio::test]
+async fn test_rate_limit_exceeded_includes_retry_after_and_limit_headers() {
+    let app = Router::new()
+        .route("/test", axum::routing::get(|| async { "OK" }))
+        .layer(RateLimitLayer::new(1, 1));
+
+    let mut response = app
+        .clone()
+        .oneshot(Request::builder().uri("/test").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for _ in 0..10 {
+        response = app
+            .clone()
+            .oneshot(Request::builder().uri("/test").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            break;
+        }
+    }
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    assert_eq!(response.headers().get("x-ratelimit-limit").unwrap(), "1");
+    assert_eq!(response.headers().get("x-ratelimit-remaining").unwrap(), "0");
+}
+
+#[tokio::test]
+async fn test_rate_limit_keys_are_isolated_per_api_key() {
+    // A very strict limit, but each distinct API key gets its own bucket
+    let app = Router::new()
+        .route("/test", axum::routing::get(|| async { "OK" }))
+        .layer(RateLimitLayer::new(1, 1));
+
+    for key in ["key-a", "key-b", "key-c"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/test")
+                    .header(AUTHORIZATION, format!("Bearer {}", key))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "key {} should have its own budget", key);
+    }
+}
+
 #[tokio::test]
 async fn test_rate_limit_burst_capacity() {
     // Allow burst of 5 requests
@@ -35,8 +35,9 @@ async fn test_batcher_with_custom_config() {
         max_batch_size: 8,
         max_wait_ms: 200,
         enabled: true,
+        ..Default::default()
     };
-    
+
     let batcher = Batcher::with_config(config);
     assert_eq!(batcher.pending_count().await, 0);
 }
@@ -47,6 +48,7 @@ async fn test_batcher_disabled() {
         max_batch_size: 4,
         max_wait_ms: 100,
         enabled: false,
+        ..Default::default()
     };
     
     let batcher = Batcher::with_config(config);